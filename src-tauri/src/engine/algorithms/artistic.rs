@@ -37,6 +37,8 @@ impl Algorithm for Artistic {
                 let y = y_idx as u32;
                 for x in 0..w {
                     let idx = (x * 4) as usize;
+                    // Fully transparent pixels are left untouched
+                    if row[idx + 3] == 0 { continue; }
                     let r = row[idx];
                     let g = row[idx + 1];
                     let b = row[idx + 2];
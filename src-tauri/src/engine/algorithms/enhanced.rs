@@ -23,6 +23,8 @@ impl Algorithm for Enhanced {
         pixels.par_chunks_mut((width * 4) as usize)
             .for_each(|row| {
                 for chunk in row.chunks_exact_mut(4) {
+                    // Fully transparent pixels are left untouched
+                    if chunk[3] == 0 { continue; }
                     let r = chunk[0];
                     let g = chunk[1];
                     let b = chunk[2];
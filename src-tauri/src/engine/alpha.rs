@@ -0,0 +1,42 @@
+use crate::engine::algorithms::RgbaImage;
+
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlphaMode {
+    /// Pass source alpha through unchanged
+    Keep,
+    /// 1-bit alpha: opaque at or above the threshold, fully transparent below
+    Threshold(u8),
+    /// 1-bit alpha using a 4x4 Bayer pattern so soft edges keep their falloff
+    Dither,
+}
+
+impl AlphaMode {
+    pub fn from_request(mode: Option<&str>, threshold: Option<u8>) -> Self {
+        match mode.map(|m| m.trim().to_ascii_lowercase()).as_deref() {
+            Some("threshold") | Some("1-bit") | Some("1bit") => AlphaMode::Threshold(threshold.unwrap_or(128)),
+            Some("dither") | Some("dithered") | Some("ordered") => AlphaMode::Dither,
+            _ => AlphaMode::Keep,
+        }
+    }
+}
+
+pub fn apply_alpha_mode(img: &mut RgbaImage, mode: AlphaMode) {
+    match mode {
+        AlphaMode::Keep => {}
+        AlphaMode::Threshold(t) => {
+            for p in img.pixels_mut() {
+                p.0[3] = if p.0[3] >= t.max(1) { 255 } else { 0 };
+            }
+        }
+        AlphaMode::Dither => {
+            for (x, y, p) in img.enumerate_pixels_mut() {
+                let a = p.0[3];
+                if a == 0 || a == 255 { continue; }
+                let t = (BAYER_4X4[(y % 4) as usize][(x % 4) as usize] as f32 + 0.5) / 16.0;
+                p.0[3] = if a as f32 / 255.0 > t { 255 } else { 0 };
+            }
+        }
+    }
+}
@@ -11,9 +11,13 @@ pub fn hex_to_rgb(hex: &str) -> Option<[u8; 3]> {
 #[inline]
 pub fn rgb_to_lab(r: u8, g: u8, b: u8) -> [f32; 3] {
     // Normalize to 0..1
-    let mut rn = r as f32 / 255.0;
-    let mut gn = g as f32 / 255.0;
-    let mut bn = b as f32 / 255.0;
+    srgb01_to_lab([r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0])
+}
+
+// rgb_to_lab for normalized (0..1) sRGB, used by the float working image
+#[inline]
+pub fn srgb01_to_lab(rgb: [f32; 3]) -> [f32; 3] {
+    let [mut rn, mut gn, mut bn] = rgb;
 
     // Inverse gamma
     rn = if rn > 0.04045 { ((rn + 0.055) / 1.055).powf(2.4) } else { rn / 12.92 };
@@ -40,6 +44,36 @@ pub fn rgb_to_lab(r: u8, g: u8, b: u8) -> [f32; 3] {
     [l, a, b]
 }
 
+// Inverse of rgb_to_lab (D65); out-of-gamut results are clamped to sRGB
+#[inline]
+pub fn lab_to_rgb(lab: [f32; 3]) -> [u8; 3] {
+    lab_to_srgb01(lab).map(|v| (v * 255.0).round().clamp(0.0, 255.0) as u8)
+}
+
+// lab_to_rgb without the 8-bit quantization (0..1 sRGB)
+#[inline]
+pub fn lab_to_srgb01(lab: [f32; 3]) -> [f32; 3] {
+    let fy = (lab[0] + 16.0) / 116.0;
+    let fx = fy + lab[1] / 500.0;
+    let fz = fy - lab[2] / 200.0;
+    let finv = |t: f32| if t * t * t > 0.008856 { t * t * t } else { (t - 16.0 / 116.0) / 7.787 };
+
+    let x = finv(fx) * 0.95047;
+    let y = finv(fy);
+    let z = finv(fz) * 1.08883;
+
+    let rl = x * 3.2404542 + y * -1.5371385 + z * -0.4985314;
+    let gl = x * -0.969266 + y * 1.8760108 + z * 0.041556;
+    let bl = x * 0.0556434 + y * -0.2040259 + z * 1.0572252;
+
+    // Gamma
+    let enc = |c: f32| {
+        let c = c.clamp(0.0, 1.0);
+        if c > 0.0031308 { 1.055 * c.powf(1.0 / 2.4) - 0.055 } else { 12.92 * c }
+    };
+    [enc(rl), enc(gl), enc(bl)]
+}
+
 #[inline]
 pub fn lab_distance(lab1: [f32; 3], lab2: [f32; 3]) -> f32 {
     let dl = lab1[0] - lab2[0];
@@ -0,0 +1,166 @@
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageReader, ImageResult, Rgba32FImage};
+use moxcms::{ColorProfile, DataColorSpace, Layout, TransformOptions};
+use std::io::Cursor;
+
+/// Decodes encoded image bytes, converting an embedded ICC profile to sRGB and applying
+/// the EXIF orientation, so every later stage sees upright sRGB pixels. 16-bit and float
+/// sources keep their native precision.
+pub fn load_image_from_memory(bytes: &[u8]) -> ImageResult<DynamicImage> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let icc = decoder.icc_profile().ok().flatten();
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    if let Some(icc) = icc {
+        if let Some(converted) = convert_icc_to_srgb(&img, &icc) {
+            img = converted;
+        }
+    }
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+// Returns None when the profile is unusable or not RGB; the image is then used as-is. 16-bit
+// sources go through the float transform but come back as 16-bit, so only float decodes are
+// treated as HDR later
+fn convert_icc_to_srgb(img: &DynamicImage, icc: &[u8]) -> Option<DynamicImage> {
+    let src_profile = ColorProfile::new_from_slice(icc).ok()?;
+    if src_profile.color_space != DataColorSpace::Rgb {
+        return None;
+    }
+    let srgb = ColorProfile::new_srgb();
+    if is_high_precision(img) {
+        let transform = src_profile
+            .create_transform_f32(Layout::Rgba, &srgb, Layout::Rgba, TransformOptions::default())
+            .ok()?;
+        let src = img.to_rgba32f();
+        let mut dst = Rgba32FImage::new(src.width(), src.height());
+        transform.transform(src.as_raw(), &mut dst).ok()?;
+        let dst = DynamicImage::ImageRgba32F(dst);
+        return Some(if is_hdr(img) { dst } else { DynamicImage::ImageRgba16(dst.to_rgba16()) });
+    }
+    let transform = src_profile
+        .create_transform_8bit(Layout::Rgba, &srgb, Layout::Rgba, TransformOptions::default())
+        .ok()?;
+    let src = img.to_rgba8();
+    let mut dst = image::RgbaImage::new(src.width(), src.height());
+    transform.transform(src.as_raw(), &mut dst).ok()?;
+    Some(DynamicImage::ImageRgba8(dst))
+}
+
+fn is_high_precision(img: &DynamicImage) -> bool {
+    !matches!(
+        img,
+        DynamicImage::ImageLuma8(_) | DynamicImage::ImageLumaA8(_) | DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_)
+    )
+}
+
+// Float decodes (OpenEXR, Radiance HDR, float TIFF) carry scene-linear values that may exceed 1.0
+fn is_hdr(img: &DynamicImage) -> bool {
+    matches!(img, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HdrToneMap {
+    /// Reinhard on luminance, L / (1 + L); keeps hue, rolls off highlights gently
+    Reinhard,
+    /// Narkowicz fit of the ACES filmic curve; more contrast and a softer shoulder
+    Aces,
+    /// Hard clip at 1.0
+    Clip,
+}
+
+impl HdrToneMap {
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name.map(|n| n.trim().to_ascii_lowercase()).as_deref() {
+            Some("aces") | Some("filmic") => Self::Aces,
+            Some("clip") | Some("none") => Self::Clip,
+            _ => Self::Reinhard,
+        }
+    }
+
+    fn map(self, rgb: [f32; 3]) -> [f32; 3] {
+        match self {
+            Self::Reinhard => {
+                let l = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+                if l <= 0.0 { return [0.0; 3]; }
+                let scale = (l / (1.0 + l)) / l;
+                rgb.map(|c| c * scale)
+            }
+            Self::Aces => rgb.map(|c| {
+                let x = c * 0.6;
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }),
+            Self::Clip => rgb,
+        }
+    }
+}
+
+#[inline]
+fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c > 0.0031308 { 1.055 * c.powf(1.0 / 2.4) - 0.055 } else { 12.92 * c }
+}
+
+/// Converts a decoded image to the float working image (sRGB-encoded RGBA, 0..1) used from
+/// the prefilters through the grid resize. HDR sources are exposed, tone mapped and encoded
+/// to sRGB first; everything else is only widened, so 16-bit gradients survive intact.
+pub fn to_working_image(img: DynamicImage, tone_map: HdrToneMap, exposure: f32) -> Rgba32FImage {
+    let hdr = is_hdr(&img);
+    let mut work = match img {
+        DynamicImage::ImageRgba32F(buf) => buf,
+        other => other.to_rgba32f(),
+    };
+    if hdr {
+        let gain = if exposure.is_finite() { exposure.exp2() } else { 1.0 };
+        for p in work.pixels_mut() {
+            let [r, g, b, a] = p.0;
+            let lin = [r, g, b].map(|c| if c.is_finite() { c.max(0.0) * gain } else { 0.0 });
+            let mapped = tone_map.map(lin);
+            p.0 = [
+                linear_to_srgb(mapped[0]),
+                linear_to_srgb(mapped[1]),
+                linear_to_srgb(mapped[2]),
+                if a.is_finite() { a.clamp(0.0, 1.0) } else { 1.0 },
+            ];
+        }
+    }
+    work
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::png::PngEncoder;
+    use image::{ExtendedColorType, ImageEncoder};
+
+    // 2x1 16-bit RGB PNG: white, then mid-gray
+    fn png16(icc: Option<Vec<u8>>) -> Vec<u8> {
+        let samples: Vec<u8> = [0xFFFFu16, 0xFFFF, 0xFFFF, 0x8000, 0x8000, 0x8000]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        let mut bytes = Vec::new();
+        let mut encoder = PngEncoder::new(&mut bytes);
+        if let Some(icc) = icc {
+            encoder.set_icc_profile(icc).unwrap();
+        }
+        encoder.write_image(&samples, 2, 1, ExtendedColorType::Rgb16).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn srgb_tagged_16_bit_source_is_not_tone_mapped() {
+        let icc = ColorProfile::new_srgb().encode().unwrap();
+        for bytes in [png16(None), png16(Some(icc))] {
+            let img = load_image_from_memory(&bytes).unwrap();
+            assert!(is_high_precision(&img) && !is_hdr(&img));
+            let work = to_working_image(img, HdrToneMap::Reinhard, 0.0);
+            let (white, gray) = (work.get_pixel(0, 0).0, work.get_pixel(1, 0).0);
+            assert!(white[..3].iter().all(|&c| (c - 1.0).abs() < 0.01), "{white:?}");
+            assert!(gray[..3].iter().all(|&c| (c - 0.5).abs() < 0.01), "{gray:?}");
+        }
+    }
+}
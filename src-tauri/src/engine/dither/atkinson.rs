@@ -27,13 +27,15 @@ pub fn apply_atkinson(img: &mut RgbaImage, palette: &[[u8;3]]) {
             let (r,g,b,a) = (p[0], p[1], p[2], p[3]);
             let chosen = closest(r,g,b,palette);
             img.put_pixel(x as u32, y as u32, Rgba([chosen[0],chosen[1],chosen[2],a]));
+            // Fully transparent pixels neither emit nor receive diffusion error
+            if a == 0 { continue; }
             let er = r as i16 - chosen[0] as i16;
             let eg = g as i16 - chosen[1] as i16;
             let eb = b as i16 - chosen[2] as i16;
 
             let spread = |dx: i32, dy: i32, buf: &mut RgbaImage| {
                 let nx = x + dx; let ny = y + dy;
-                if nx >= 0 && nx < w && ny >= 0 && ny < h {
+                if nx >= 0 && nx < w && ny >= 0 && ny < h && buf.get_pixel(nx as u32, ny as u32).0[3] != 0 {
                     let mut q = buf.get_pixel(nx as u32, ny as u32).0;
                     let add = |c: u8, e: i16| -> u8 { (c as i16 + e / 8).clamp(0,255) as u8 };
                     q[0] = add(q[0], er); q[1] = add(q[1], eg); q[2] = add(q[2], eb);
@@ -3,11 +3,11 @@ use image::Rgba;
 use crate::engine::algorithms::{Algorithm, RgbaImage};
 use crate::engine::color::{brightness, lab_distance, rgb_to_lab};
 
-const BAYER_2X2: [[u8; 2]; 2] = [[0, 2], [3, 1]];
+pub(crate) const BAYER_2X2: [[u8; 2]; 2] = [[0, 2], [3, 1]];
 
-const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+pub(crate) const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
 
-const BAYER_8X8: [[u8; 8]; 8] = [
+pub(crate) const BAYER_8X8: [[u8; 8]; 8] = [
     [0, 48, 12, 60, 3, 51, 15, 63],
     [32, 16, 44, 28, 35, 19, 47, 31],
     [8, 56, 4, 52, 11, 59, 7, 55],
@@ -0,0 +1,144 @@
+use image::Rgba;
+
+use super::bayer::{BAYER_2X2, BAYER_4X4, BAYER_8X8};
+use crate::engine::algorithms::RgbaImage;
+
+/// An error diffusion kernel as (dx, dy, weight) for a left-to-right pass, with its divisor.
+pub(crate) type Kernel = (&'static [(i32, i32, f32)], f32);
+
+const FLOYD_STEINBERG: Kernel = (&[(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)], 16.0);
+const STUCKI: Kernel = (
+    &[
+        (1, 0, 8.0), (2, 0, 4.0),
+        (-2, 1, 2.0), (-1, 1, 4.0), (0, 1, 8.0), (1, 1, 4.0), (2, 1, 2.0),
+        (-2, 2, 1.0), (-1, 2, 2.0), (0, 2, 4.0), (1, 2, 2.0), (2, 2, 1.0),
+    ],
+    42.0,
+);
+const ATKINSON: Kernel = (
+    &[(1, 0, 1.0), (2, 0, 1.0), (-1, 1, 1.0), (0, 1, 1.0), (1, 1, 1.0), (0, 2, 1.0)],
+    8.0,
+);
+const JJN: Kernel = (
+    &[
+        (1, 0, 7.0), (2, 0, 5.0),
+        (-2, 1, 3.0), (-1, 1, 5.0), (0, 1, 7.0), (1, 1, 5.0), (2, 1, 3.0),
+        (-2, 2, 1.0), (-1, 2, 3.0), (0, 2, 5.0), (1, 2, 3.0), (2, 2, 1.0),
+    ],
+    48.0,
+);
+const BURKES: Kernel = (
+    &[(1, 0, 8.0), (2, 0, 4.0), (-2, 1, 2.0), (-1, 1, 4.0), (0, 1, 8.0), (1, 1, 4.0), (2, 1, 2.0)],
+    32.0,
+);
+const SIERRA: Kernel = (
+    &[
+        (1, 0, 5.0), (2, 0, 3.0),
+        (-2, 1, 2.0), (-1, 1, 4.0), (0, 1, 5.0), (1, 1, 4.0), (2, 1, 2.0),
+        (-1, 2, 2.0), (0, 2, 3.0), (1, 2, 2.0),
+    ],
+    32.0,
+);
+const TWO_ROW_SIERRA: Kernel = (
+    &[(1, 0, 4.0), (2, 0, 3.0), (-2, 1, 1.0), (-1, 1, 2.0), (0, 1, 3.0), (1, 1, 2.0), (2, 1, 1.0)],
+    16.0,
+);
+const SIERRA_LITE: Kernel = (&[(1, 0, 2.0), (-1, 1, 1.0), (0, 1, 1.0)], 4.0);
+
+// Nearest level (0..levels-1) for an 8-bit channel value
+fn level_of(v: f32, levels: u16) -> u16 {
+    let top = (levels - 1) as f32;
+    (v / 255.0 * top).round().clamp(0.0, top) as u16
+}
+
+/// The 8-bit value a channel level stands for, spreading the levels evenly over 0..255.
+pub fn level_value(level: u16, levels: u16) -> u8 {
+    let top = (levels.max(2) - 1) as u32;
+    ((level as u32 * 255 + top / 2) / top) as u8
+}
+
+/// Quantizes each channel of `img` on its own to `levels[ch]` evenly spaced values, with no
+/// palette search. Bayer names dither with their threshold matrix, the error diffusion names
+/// with their kernel applied per channel (serpentine, as in the palette versions); other
+/// names round to the nearest level. Returns each pixel's channel levels, row-major.
+pub fn apply_channel_dither_by_name(img: &mut RgbaImage, algorithm: &str, levels: [u16; 3]) -> Vec<[u16; 3]> {
+    let levels = levels.map(|l| l.max(2));
+    match algorithm {
+        "Bayer" => ordered(img, levels, &BAYER_4X4),
+        "Bayer 2x2" => ordered(img, levels, &BAYER_2X2),
+        "Bayer 8x8" => ordered(img, levels, &BAYER_8X8),
+        _ => diffuse(img, levels, kernel_by_name(algorithm).unwrap_or((&[], 1.0))),
+    }
+}
+
+/// The error diffusion kernel of a dither algorithm name, None for algorithms that don't
+/// diffuse with a plain kernel.
+pub(crate) fn kernel_by_name(algorithm: &str) -> Option<Kernel> {
+    match algorithm {
+        "Floyd-Steinberg" | "Floyd–Steinberg" => Some(FLOYD_STEINBERG),
+        "Stucki" => Some(STUCKI),
+        "Atkinson" => Some(ATKINSON),
+        "Jarvis-Judice-Ninke" | "Jarvis, Judice, and Ninke" => Some(JJN),
+        "Burkes" => Some(BURKES),
+        "Sierra" => Some(SIERRA),
+        "Two-Row Sierra" => Some(TWO_ROW_SIERRA),
+        "Sierra Lite" => Some(SIERRA_LITE),
+        _ => None,
+    }
+}
+
+fn put_levels(img: &mut RgbaImage, x: u32, y: u32, q: [u16; 3], levels: [u16; 3]) {
+    let a = img.get_pixel(x, y).0[3];
+    let [r, g, b] = [0, 1, 2].map(|ch| level_value(q[ch], levels[ch]));
+    img.put_pixel(x, y, Rgba([r, g, b, a]));
+}
+
+// Offsets each channel by the matrix threshold, scaled to one level step, before rounding
+fn ordered<const N: usize>(img: &mut RgbaImage, levels: [u16; 3], matrix: &[[u8; N]; N]) -> Vec<[u16; 3]> {
+    let (w, h) = img.dimensions();
+    let denom = (N * N) as f32;
+    let mut out = Vec::with_capacity((w * h) as usize);
+    for y in 0..h {
+        for x in 0..w {
+            let p = img.get_pixel(x, y).0;
+            let t = (matrix[y as usize % N][x as usize % N] as f32 + 0.5) / denom - 0.5;
+            let q = [0, 1, 2].map(|ch| {
+                let step = 255.0 / (levels[ch] - 1) as f32;
+                level_of(p[ch] as f32 + t * step, levels[ch])
+            });
+            put_levels(img, x, y, q, levels);
+            out.push(q);
+        }
+    }
+    out
+}
+
+fn diffuse(img: &mut RgbaImage, levels: [u16; 3], (kernel, divisor): Kernel) -> Vec<[u16; 3]> {
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    let mut buf: Vec<[f32; 3]> = img.pixels().map(|p| [p.0[0] as f32, p.0[1] as f32, p.0[2] as f32]).collect();
+    let mut out = vec![[0u16; 3]; buf.len()];
+    for y in 0..h {
+        let ltr = (y % 2) == 0;
+        let xr: Box<dyn Iterator<Item = i32>> = if ltr { Box::new(0..w) } else { Box::new((0..w).rev()) };
+        for x in xr {
+            let i = (y * w + x) as usize;
+            let v = buf[i];
+            let q = [0, 1, 2].map(|ch| level_of(v[ch], levels[ch]));
+            put_levels(img, x as u32, y as u32, q, levels);
+            out[i] = q;
+            // Fully transparent pixels neither emit nor receive diffusion error
+            if img.get_pixel(x as u32, y as u32).0[3] == 0 { continue; }
+            let err = [0, 1, 2].map(|ch| v[ch] - level_value(q[ch], levels[ch]) as f32);
+            for &(dx, dy, weight) in kernel {
+                let nx = if ltr { x + dx } else { x - dx };
+                let ny = y + dy;
+                if nx < 0 || nx >= w || ny >= h || img.get_pixel(nx as u32, ny as u32).0[3] == 0 { continue; }
+                let n = &mut buf[(ny * w + nx) as usize];
+                for ch in 0..3 {
+                    n[ch] = (n[ch] + err[ch] * weight / divisor).clamp(0.0, 255.0);
+                }
+            }
+        }
+    }
+    out
+}
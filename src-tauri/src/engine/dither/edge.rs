@@ -45,6 +45,8 @@ pub fn apply_edge_dithering(img: &mut RgbaImage, palette: &[[u8;3]]) {
             let (r,g,b,a) = (p[0], p[1], p[2], p[3]);
             let chosen = closest_palette_color(r,g,b,palette);
             img.put_pixel(x as u32, y as u32, Rgba([chosen[0], chosen[1], chosen[2], a]));
+            // Fully transparent pixels neither emit nor receive diffusion error
+            if a == 0 { continue; }
 
             // Edge-aware: steer diffusion mostly along the edge tangent to reduce ringing across edges
             let (gx, gy) = sobel_at(&buf, x, y);
@@ -66,6 +68,7 @@ pub fn apply_edge_dithering(img: &mut RgbaImage, palette: &[[u8;3]]) {
             for (dx, dy, base_w) in neighbors.iter().copied() {
                 let nx = x + dx; let ny = y + dy;
                 if nx < 0 || ny < 0 || nx >= w || ny >= h { continue; }
+                if buf.get_pixel(nx as u32, ny as u32).0[3] == 0 { continue; }
                 // steering factor: favor along tangent when strong gradient
                 let proj = (dx as f32 * tx + dy as f32 * ty).abs();
                 let steer = if mag > 0.01 { (1.0 + proj).min(2.0) } else { 1.0 };
@@ -36,12 +36,14 @@ impl Algorithm for FloydSteinberg {
                 let (r, g, b, a) = (p[0], p[1], p[2], p[3]);
                 let chosen = find_closest_palette_color(r, g, b, palette);
                 img.put_pixel(x as u32, y as u32, Rgba([chosen[0], chosen[1], chosen[2], a]));
+                // Fully transparent pixels neither emit nor receive diffusion error
+                if a == 0 { continue; }
                 let err_r = r as i16 - chosen[0] as i16;
                 let err_g = g as i16 - chosen[1] as i16;
                 let err_b = b as i16 - chosen[2] as i16;
 
                 let distribute = |bx: i32, by: i32, factor_n: i16, factor_d: i16, buf: &mut RgbaImage| {
-                    if bx >= 0 && bx < w && by >= 0 && by < h {
+                    if bx >= 0 && bx < w && by >= 0 && by < h && buf.get_pixel(bx as u32, by as u32).0[3] != 0 {
                         let mut q = buf.get_pixel(bx as u32, by as u32).0;
                         let add = |c: u8, e: i16| -> u8 {
                             let v = c as i16 + (e * factor_n) / factor_d;
@@ -10,4 +10,40 @@ pub mod atkinson;
 pub mod jarvis_judice_ninke;
 pub mod burkes;
 pub mod sierra;
+pub mod channel;
 
+use image::Rgba;
+
+use super::algorithms::{get_algorithm_by_name, Algorithm, RgbaImage};
+
+/// Quantizes `img` to `palette` with the dither or algorithm named as in the UI. Unknown
+/// names fall back to the algorithm registry (e.g. "Standard"). Fully transparent pixels
+/// keep their RGB whichever algorithm runs.
+pub fn apply_dither_by_name(img: &mut RgbaImage, algorithm: &str, palette: &[[u8; 3]]) {
+    // The diffusion dithers quantize transparent pixels (only keeping error out of them) while
+    // the registry algorithms skip them; restore them so the hidden RGB never depends on that
+    let hidden: Vec<(u32, u32, Rgba<u8>)> =
+        img.enumerate_pixels().filter(|(_, _, p)| p.0[3] == 0).map(|(x, y, p)| (x, y, *p)).collect();
+    match algorithm {
+        "Floyd-Steinberg" | "Floyd–Steinberg" => floyd_steinberg::FloydSteinberg.process(img, palette),
+        "Bayer" => bayer::Bayer.process(img, palette),
+        "Bayer 2x2" => bayer::Bayer2.process(img, palette),
+        "Bayer 8x8" => bayer::Bayer8.process(img, palette),
+        "Selective" => selective::apply_selective(img, palette, 25.0),
+        "Ordered Selective" => ordered_selective::apply_ordered_selective(img, palette, 25.0),
+        "Dual Color Dithering" => dual_color::apply_dual_color(img, palette),
+        "Edge Dithering" => edge::apply_edge_dithering(img, palette),
+        "Randomized Selective" => randomized_selective::apply_randomized_selective(img, palette, 30.0),
+        "Stucki" => stucki::apply_stucki(img, palette),
+        "Atkinson" => atkinson::apply_atkinson(img, palette),
+        "Jarvis-Judice-Ninke" | "Jarvis, Judice, and Ninke" => jarvis_judice_ninke::apply_jjn(img, palette),
+        "Burkes" => burkes::apply_burkes(img, palette),
+        "Sierra" => sierra::apply_sierra(img, palette),
+        "Two-Row Sierra" => sierra::apply_two_row_sierra(img, palette),
+        "Sierra Lite" => sierra::apply_sierra_lite(img, palette),
+        _ => get_algorithm_by_name(algorithm).process(img, palette),
+    }
+    for (x, y, p) in hidden {
+        img.put_pixel(x, y, p);
+    }
+}
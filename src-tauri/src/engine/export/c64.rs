@@ -0,0 +1,95 @@
+use crate::engine::hardware::c64::C64Bitmap;
+
+// Default load addresses of the formats
+const KOALA_LOAD: u16 = 0x6000;
+const ART_STUDIO_LOAD: u16 = 0x2000;
+// Bytes between the Advanced Art Studio border/background pair and its color RAM
+const AAS_GAP: usize = 14;
+// Trailing bytes of an Art Studio file after the border color
+const ART_STUDIO_PAD: usize = 6;
+
+/// Koala Painter (.koa, 10003 bytes): load address $6000, bitmap, screen RAM, color RAM,
+/// background color. Multicolor only.
+pub fn encode_koala(bitmap: &C64Bitmap) -> Vec<u8> {
+    let mut out = Vec::with_capacity(10003);
+    out.extend_from_slice(&KOALA_LOAD.to_le_bytes());
+    out.extend_from_slice(&bitmap.bitmap);
+    out.extend_from_slice(&bitmap.screen);
+    out.extend_from_slice(&bitmap.color_ram);
+    out.push(bitmap.background);
+    out
+}
+
+/// OCP Art Studio hires (.art, 9009 bytes): load address $2000, bitmap, screen RAM, border
+/// color, padding.
+pub fn encode_art_studio(bitmap: &C64Bitmap) -> Vec<u8> {
+    let mut out = Vec::with_capacity(9009);
+    out.extend_from_slice(&ART_STUDIO_LOAD.to_le_bytes());
+    out.extend_from_slice(&bitmap.bitmap);
+    out.extend_from_slice(&bitmap.screen);
+    out.push(bitmap.background);
+    out.extend_from_slice(&[0; ART_STUDIO_PAD]);
+    out
+}
+
+/// OCP Advanced Art Studio multicolor (.ocp, 10018 bytes): load address $2000, bitmap, screen
+/// RAM, border and background colors, padding, color RAM.
+pub fn encode_advanced_art_studio(bitmap: &C64Bitmap) -> Vec<u8> {
+    let mut out = Vec::with_capacity(10018);
+    out.extend_from_slice(&ART_STUDIO_LOAD.to_le_bytes());
+    out.extend_from_slice(&bitmap.bitmap);
+    out.extend_from_slice(&bitmap.screen);
+    out.push(bitmap.background);
+    out.push(bitmap.background);
+    out.extend_from_slice(&[0; AAS_GAP]);
+    out.extend_from_slice(&bitmap.color_ram);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Distinct fill per section so offsets are easy to check
+    fn multicolor_bitmap() -> C64Bitmap {
+        C64Bitmap {
+            multicolor: true,
+            bitmap: vec![0xAA; 8000],
+            screen: vec![0x12; 1000],
+            color_ram: vec![0x03; 1000],
+            background: 0x06,
+        }
+    }
+
+    #[test]
+    fn koala_layout() {
+        let koa = encode_koala(&multicolor_bitmap());
+        assert_eq!(koa.len(), 10003);
+        assert_eq!(koa[..2], [0x00, 0x60]);
+        assert_eq!((koa[2], koa[8001]), (0xAA, 0xAA));
+        assert_eq!((koa[8002], koa[9001]), (0x12, 0x12));
+        assert_eq!((koa[9002], koa[10001]), (0x03, 0x03));
+        assert_eq!(koa[10002], 0x06);
+    }
+
+    #[test]
+    fn advanced_art_studio_layout() {
+        let ocp = encode_advanced_art_studio(&multicolor_bitmap());
+        assert_eq!(ocp.len(), 10018);
+        assert_eq!(ocp[..2], [0x00, 0x20]);
+        assert_eq!((ocp[2], ocp[8001]), (0xAA, 0xAA));
+        assert_eq!((ocp[8002], ocp[9001]), (0x12, 0x12));
+        assert_eq!(ocp[9002..9004], [0x06, 0x06]);
+        assert!(ocp[9004..9018].iter().all(|&b| b == 0));
+        assert_eq!((ocp[9018], ocp[10017]), (0x03, 0x03));
+    }
+
+    #[test]
+    fn art_studio_layout() {
+        let art = encode_art_studio(&C64Bitmap { multicolor: false, color_ram: vec![0; 1000], ..multicolor_bitmap() });
+        assert_eq!(art.len(), 9009);
+        assert_eq!(art[..2], [0x00, 0x20]);
+        assert_eq!((art[9001], art[9002]), (0x12, 0x06));
+        assert!(art[9003..].iter().all(|&b| b == 0));
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt::Write;
+
+use super::{push_c_array, push_rust_array};
+use crate::engine::hardware::display::{DisplayFormat, DisplayImage};
+
+// Palette indices of the three-color e-paper format
+const BWR_BLACK: u16 = 1;
+const BWR_RED: u16 = 2;
+
+// 1bpp with 8 horizontal pixels per byte, leftmost in bit 7, rows padded to a whole byte
+fn pack_horizontal(img: &DisplayImage, set: impl Fn(u16) -> bool) -> Vec<u8> {
+    let (w, h) = (img.width as usize, img.height as usize);
+    let row_len = w.div_ceil(8);
+    let mut out = vec![0u8; row_len * h];
+    for (i, &p) in img.pixels.iter().enumerate() {
+        if set(p) {
+            let (x, y) = (i % w, i / w);
+            out[y * row_len + x / 8] |= 0x80 >> (x % 8);
+        }
+    }
+    out
+}
+
+/// Packs a display buffer into the bytes the controller expects, as named planes: a single
+/// unnamed plane, or "black" and "red" for three-color e-paper. Set 1bpp bits are lit
+/// (white) pixels; partial bytes at the right or bottom edge are zero-padded.
+pub fn encode_display_planes(img: &DisplayImage) -> Vec<(&'static str, Vec<u8>)> {
+    let (w, h) = (img.width as usize, img.height as usize);
+    let bytes = match img.format {
+        DisplayFormat::MonoHorizontal => pack_horizontal(img, |p| p == 1),
+        DisplayFormat::MonoVertical => {
+            let col_len = h.div_ceil(8);
+            let mut out = vec![0u8; col_len * w];
+            for (i, &p) in img.pixels.iter().enumerate() {
+                if p == 1 {
+                    let (x, y) = (i % w, i / w);
+                    out[x * col_len + y / 8] |= 0x80 >> (y % 8);
+                }
+            }
+            out
+        }
+        DisplayFormat::MonoPage => {
+            let mut out = vec![0u8; h.div_ceil(8) * w];
+            for (i, &p) in img.pixels.iter().enumerate() {
+                if p == 1 {
+                    let (x, y) = (i % w, i / w);
+                    out[(y / 8) * w + x] |= 1 << (y % 8);
+                }
+            }
+            out
+        }
+        DisplayFormat::Gray2 => {
+            let row_len = w.div_ceil(4);
+            let mut out = vec![0u8; row_len * h];
+            for (i, &p) in img.pixels.iter().enumerate() {
+                let (x, y) = (i % w, i / w);
+                out[y * row_len + x / 4] |= (p as u8 & 3) << (6 - 2 * (x % 4));
+            }
+            out
+        }
+        DisplayFormat::Rgb565 { big_endian } => img
+            .pixels
+            .iter()
+            .flat_map(|&p| if big_endian { p.to_be_bytes() } else { p.to_le_bytes() })
+            .collect(),
+        DisplayFormat::BlackWhiteRed => {
+            // Black/white RAM clears bits for black; red RAM sets bits for red
+            return vec![
+                ("black", pack_horizontal(img, |p| p != BWR_BLACK)),
+                ("red", pack_horizontal(img, |p| p == BWR_RED)),
+            ];
+        }
+    };
+    vec![("", bytes)]
+}
+
+fn plane_name(name: &str, plane: &str) -> String {
+    if plane.is_empty() { name.to_string() } else { format!("{name}_{plane}") }
+}
+
+/// C header declaring the buffer dimensions and one `static const uint8_t` array per plane,
+/// so including it from several files doesn't define the arrays twice.
+pub fn display_c_source(img: &DisplayImage, name: &str) -> String {
+    let mut out = String::new();
+    let upper = name.to_ascii_uppercase();
+    let _ = writeln!(out, "// {}x{} display buffer: {}", img.width, img.height, img.format.label());
+    out.push_str("#pragma once\n#include <stdint.h>\n\n");
+    let _ = writeln!(out, "#define {upper}_WIDTH {}", img.width);
+    let _ = writeln!(out, "#define {upper}_HEIGHT {}\n", img.height);
+    for (i, (plane, bytes)) in encode_display_planes(img).into_iter().enumerate() {
+        if i > 0 { out.push('\n'); }
+        push_c_array(&mut out, "static const uint8_t", &plane_name(name, plane), bytes.iter().map(|b| format!("0x{b:02X}")));
+    }
+    out
+}
+
+/// Rust module declaring the buffer dimensions and one `u8` static per plane.
+pub fn display_rust_source(img: &DisplayImage, name: &str) -> String {
+    let mut out = String::new();
+    let upper = name.to_ascii_uppercase();
+    let _ = writeln!(out, "// {}x{} display buffer: {}\n", img.width, img.height, img.format.label());
+    let _ = writeln!(out, "pub const {upper}_WIDTH: u32 = {};", img.width);
+    let _ = writeln!(out, "pub const {upper}_HEIGHT: u32 = {};\n", img.height);
+    for (i, (plane, bytes)) in encode_display_planes(img).into_iter().enumerate() {
+        if i > 0 { out.push('\n'); }
+        let array = plane_name(&upper, &plane.to_ascii_uppercase());
+        push_rust_array(&mut out, "u8", &array, bytes.iter().map(|b| format!("0x{b:02X}")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A buffer of `format` with value 1 at the `lit` pixels and 0 elsewhere
+    fn image(format: DisplayFormat, width: u32, height: u32, lit: &[(u32, u32)]) -> DisplayImage {
+        let mut pixels = vec![0u16; (width * height) as usize];
+        for &(x, y) in lit {
+            pixels[(y * width + x) as usize] = 1;
+        }
+        DisplayImage { format, width, height, pixels }
+    }
+
+    fn single_plane(img: &DisplayImage) -> Vec<u8> {
+        let planes = encode_display_planes(img);
+        assert_eq!(planes.len(), 1);
+        assert_eq!(planes[0].0, "");
+        planes[0].1.clone()
+    }
+
+    #[test]
+    fn mono_horizontal_is_msb_left_with_padded_rows() {
+        let img = image(DisplayFormat::MonoHorizontal, 10, 2, &[(0, 0), (9, 0), (1, 1)]);
+        assert_eq!(single_plane(&img), [0x80, 0x40, 0x40, 0x00]);
+    }
+
+    #[test]
+    fn mono_vertical_is_msb_top_column_by_column() {
+        let img = image(DisplayFormat::MonoVertical, 2, 10, &[(0, 0), (0, 9), (1, 1)]);
+        assert_eq!(single_plane(&img), [0x80, 0x40, 0x40, 0x00]);
+    }
+
+    #[test]
+    fn mono_page_is_lsb_top_along_each_page() {
+        let img = image(DisplayFormat::MonoPage, 2, 10, &[(0, 0), (0, 9), (1, 1)]);
+        assert_eq!(single_plane(&img), [0x01, 0x02, 0x02, 0x00]);
+    }
+
+    #[test]
+    fn gray_2bpp_packs_four_pixels_per_byte() {
+        let mut img = image(DisplayFormat::Gray2, 5, 1, &[]);
+        img.pixels = vec![0, 1, 2, 3, 3];
+        assert_eq!(single_plane(&img), [0b00_01_10_11, 0b11_00_00_00]);
+    }
+
+    #[test]
+    fn rgb565_byte_order() {
+        let mut img = image(DisplayFormat::Rgb565 { big_endian: true }, 1, 1, &[]);
+        img.pixels = vec![0xF81F];
+        assert_eq!(single_plane(&img), [0xF8, 0x1F]);
+        img.format = DisplayFormat::Rgb565 { big_endian: false };
+        assert_eq!(single_plane(&img), [0x1F, 0xF8]);
+    }
+
+    #[test]
+    fn black_white_red_inverts_the_black_plane() {
+        let mut img = image(DisplayFormat::BlackWhiteRed, 3, 1, &[]);
+        img.pixels = vec![0, BWR_BLACK, BWR_RED];
+        let planes = encode_display_planes(&img);
+        assert_eq!(planes, [("black", vec![0b1010_0000]), ("red", vec![0b0010_0000])]);
+    }
+}
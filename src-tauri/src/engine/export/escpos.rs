@@ -0,0 +1,63 @@
+// Rows per GS v 0 command; small bands keep within the receive buffer of cheap printers
+const BAND_ROWS: usize = 256;
+// Lines fed after the image so it clears the tear bar
+const FEED_LINES: u8 = 4;
+
+/// Encodes a 1-bit image as an ESC/POS print job: ESC @ (initialize), then the image as
+/// `GS v 0` raster bit images in bands of up to 256 rows (8 dots per byte, leftmost in
+/// bit 7, set bits burned), then ESC d to feed past the tear bar and GS V 1 for a partial
+/// cut (ignored by printers without a cutter).
+pub fn encode_escpos_raster(width: u32, height: u32, dots: &[bool]) -> Vec<u8> {
+    let (w, h) = (width as usize, height as usize);
+    let row_len = w.div_ceil(8);
+    let mut out = vec![0x1B, 0x40];
+    for band in (0..h).step_by(BAND_ROWS) {
+        let rows = BAND_ROWS.min(h - band);
+        // GS v 0 m xL xH yL yH, m = 0 for normal density
+        out.extend_from_slice(&[0x1D, 0x76, 0x30, 0x00]);
+        out.extend_from_slice(&(row_len as u16).to_le_bytes());
+        out.extend_from_slice(&(rows as u16).to_le_bytes());
+        for y in band..band + rows {
+            let row = &dots[y * w..(y + 1) * w];
+            for chunk in row.chunks(8) {
+                let mut byte = 0u8;
+                for (bit, &burn) in chunk.iter().enumerate() {
+                    if burn {
+                        byte |= 0x80 >> bit;
+                    }
+                }
+                out.push(byte);
+            }
+        }
+    }
+    out.extend_from_slice(&[0x1B, 0x64, FEED_LINES]);
+    out.extend_from_slice(&[0x1D, 0x56, 0x01]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raster_header_rows_and_trailer() {
+        // 10 dots wide, 2 rows: leftmost and ninth dot burned on row 0
+        let mut dots = vec![false; 20];
+        dots[0] = true;
+        dots[8] = true;
+        let job = encode_escpos_raster(10, 2, &dots);
+        assert_eq!(job[..2], [0x1B, 0x40]);
+        assert_eq!(job[2..10], [0x1D, 0x76, 0x30, 0x00, 2, 0, 2, 0]);
+        assert_eq!(job[10..14], [0x80, 0x80, 0x00, 0x00]);
+        assert_eq!(job[14..], [0x1B, 0x64, FEED_LINES, 0x1D, 0x56, 0x01]);
+    }
+
+    #[test]
+    fn splits_tall_images_into_bands() {
+        let job = encode_escpos_raster(576, 300, &vec![false; 576 * 300]);
+        assert_eq!(job[2..10], [0x1D, 0x76, 0x30, 0x00, 72, 0, 0x00, 0x01]);
+        let second = 10 + 72 * BAND_ROWS;
+        assert_eq!(job[second..second + 8], [0x1D, 0x76, 0x30, 0x00, 72, 0, 44, 0]);
+        assert_eq!(job.len(), 2 + 2 * 8 + 72 * 300 + 6);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::engine::hardware::direct::{DirectFormat, DirectImage};
+
+/// Packs a direct-color image into raw framebuffer bytes, rows top to bottom with no header.
+/// RGB332 is one byte per pixel; the 16-bit formats are little-endian words; grayscale packs
+/// its level numbers at the format's bits per pixel, leftmost pixel in the high bits, with
+/// each row padded to a whole byte.
+pub fn encode_framebuffer(img: &DirectImage) -> Vec<u8> {
+    let [_, gb, bb] = img.format.channel_bits();
+    let word = |[r, g, b]: [u16; 3]| (r << (gb + bb)) | (g << bb) | b;
+    match img.format {
+        DirectFormat::Rgb332 => img.levels.iter().map(|&q| word(q) as u8).collect(),
+        DirectFormat::Rgb444 | DirectFormat::Rgb555 | DirectFormat::Rgb565 => {
+            img.levels.iter().flat_map(|&q| word(q).to_le_bytes()).collect()
+        }
+        DirectFormat::Gray(_) => {
+            let bpp = img.format.bits_per_pixel() as usize;
+            let w = img.width as usize;
+            let row_len = (w * bpp).div_ceil(8);
+            let mut out = vec![0u8; row_len * img.height as usize];
+            for (i, q) in img.levels.iter().enumerate() {
+                let (y, x) = (i / w, i % w);
+                let bit = x * bpp;
+                out[y * row_len + bit / 8] |= (q[0] as u8) << (8 - bpp - bit % 8);
+            }
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(format: DirectFormat, width: u32, height: u32, levels: Vec<[u16; 3]>) -> DirectImage {
+        DirectImage { format, width, height, levels }
+    }
+
+    #[test]
+    fn packs_rgb_words() {
+        let rgb332 = image(DirectFormat::Rgb332, 2, 1, vec![[7, 0, 3], [1, 2, 1]]);
+        assert_eq!(encode_framebuffer(&rgb332), [0b111_000_11, 0b001_010_01]);
+        // RRRRRGGG GGGBBBBB, low byte first
+        let rgb565 = image(DirectFormat::Rgb565, 1, 1, vec![[0x1F, 0x01, 0x02]]);
+        assert_eq!(encode_framebuffer(&rgb565), [0x22, 0xF8]);
+        let rgb444 = image(DirectFormat::Rgb444, 1, 1, vec![[0xA, 0xB, 0xC]]);
+        assert_eq!(encode_framebuffer(&rgb444), [0xBC, 0x0A]);
+    }
+
+    #[test]
+    fn packs_gray_levels_msb_first_with_row_padding() {
+        // 4 levels -> 2 bits per pixel; 5 pixels need 10 bits, padded to 2 bytes per row
+        let levels = [3, 0, 1, 2, 3, 1, 1, 1, 1, 0].map(|v| [v; 3]).to_vec();
+        let gray = image(DirectFormat::Gray(4), 5, 2, levels);
+        assert_eq!(encode_framebuffer(&gray), [0b11_00_01_10, 0b11_000000, 0b01_01_01_01, 0b00_000000]);
+        // 2 levels -> 1 bit per pixel
+        let mono = image(DirectFormat::Gray(2), 3, 1, vec![[1; 3], [0; 3], [1; 3]]);
+        assert_eq!(encode_framebuffer(&mono), [0b101_00000]);
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt::Write;
+
+use super::push_c_array;
+use crate::engine::hardware::gameboy::{GbTiles, MAP_TILES};
+
+/// Encodes tiles in the Game Boy 2bpp format: 16 bytes per tile, each row as its low bit
+/// plane byte followed by its high bit plane byte, leftmost pixel in bit 7.
+pub fn encode_2bpp(tiles: &GbTiles) -> Vec<u8> {
+    let mut out = Vec::with_capacity(tiles.tiles.len() * 16);
+    for tile in &tiles.tiles {
+        for row in tile.chunks(8) {
+            let (mut lo, mut hi) = (0u8, 0u8);
+            for (col, &v) in row.iter().enumerate() {
+                lo |= (v & 1) << (7 - col);
+                hi |= ((v >> 1) & 1) << (7 - col);
+            }
+            out.push(lo);
+            out.push(hi);
+        }
+    }
+    out
+}
+
+/// Whether the map needs 16-bit entries (more unique tiles than one map can address).
+pub fn wide_map(tiles: &GbTiles) -> bool {
+    tiles.tiles.len() > MAP_TILES
+}
+
+/// Encodes the tile map row-major: one byte per entry, or 16-bit little-endian entries
+/// when there are more than 256 unique tiles (written as `tilemap_16le.bin` so the
+/// format change is visible).
+pub fn encode_tilemap(tiles: &GbTiles) -> Vec<u8> {
+    if wide_map(tiles) {
+        tiles.map.iter().flat_map(|i| i.to_le_bytes()).collect()
+    } else {
+        tiles.map.iter().map(|&i| i as u8).collect()
+    }
+}
+
+/// C source with the tile data and tile map as arrays, plus their dimensions.
+pub fn tilemap_c_source(tiles: &GbTiles, name: &str) -> String {
+    let mut out = String::new();
+    let upper = name.to_ascii_uppercase();
+    let _ = writeln!(out, "// Game Boy background: {} tiles, {}x{} map", tiles.tiles.len(), tiles.map_width, tiles.map_height);
+    let _ = writeln!(out, "#define {upper}_TILE_COUNT {}", tiles.tiles.len());
+    let _ = writeln!(out, "#define {upper}_MAP_WIDTH {}", tiles.map_width);
+    let _ = writeln!(out, "#define {upper}_MAP_HEIGHT {}\n", tiles.map_height);
+    let data = encode_2bpp(tiles);
+    push_c_array(&mut out, "const unsigned char", &format!("{name}_tiles"), data.iter().map(|b| format!("0x{b:02X}")));
+    out.push('\n');
+    if wide_map(tiles) {
+        push_c_array(&mut out, "const unsigned short", &format!("{name}_map"), tiles.map.iter().map(|i| format!("0x{i:04X}")));
+    } else {
+        push_c_array(&mut out, "const unsigned char", &format!("{name}_map"), tiles.map.iter().map(|i| format!("0x{i:02X}")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::hardware::gameboy::DMG_SHADES;
+
+    fn tiles(tiles: Vec<[u8; 64]>, map: Vec<u16>) -> GbTiles {
+        GbTiles { shades: DMG_SHADES, tiles, map, map_width: 2, map_height: 1 }
+    }
+
+    #[test]
+    fn two_bpp_interleaves_low_and_high_byte_per_row() {
+        let mut tile = [0u8; 64];
+        tile[0] = 1; // row 0, leftmost: low bit only
+        tile[7] = 2; // row 0, rightmost: high bit only
+        tile[8 * 5 + 2] = 3; // row 5, third pixel: both bits
+        let data = encode_2bpp(&tiles(vec![[0; 64], tile], vec![0, 1]));
+        assert_eq!(data.len(), 32);
+        assert!(data[..16].iter().all(|&b| b == 0));
+        let t = &data[16..];
+        assert_eq!(t[0..2], [0x80, 0x01]);
+        assert_eq!(t[10..12], [0x20, 0x20]);
+        assert_eq!(t.iter().filter(|&&b| b != 0).count(), 4);
+    }
+
+    #[test]
+    fn tilemap_is_bytes_up_to_256_tiles_then_16_bit_le() {
+        assert_eq!(encode_tilemap(&tiles(vec![[0; 64]; 2], vec![0, 1])), [0, 1]);
+        let wide = tiles(vec![[0; 64]; 300], vec![1, 0x012B]);
+        assert!(wide_map(&wide));
+        assert_eq!(encode_tilemap(&wide), [0x01, 0x00, 0x2B, 0x01]);
+    }
+}
@@ -0,0 +1,159 @@
+/// CAMG viewport mode flag for Hold-And-Modify
+pub const CAMG_HAM: u32 = 0x0800;
+
+// Appends an IFF chunk, padded to an even length
+fn push_chunk(out: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(id);
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+// ByteRun1 (PackBits) compression of one plane row
+fn byte_run1(row: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < row.len() {
+        let mut run = 1;
+        while i + run < row.len() && run < 128 && row[i + run] == row[i] {
+            run += 1;
+        }
+        if run >= 2 {
+            out.push((257 - run) as u8);
+            out.push(row[i]);
+            i += run;
+            continue;
+        }
+        // Literal span up to the next run of at least two
+        let start = i;
+        while i < row.len() && i - start < 128 && !(i + 1 < row.len() && row[i] == row[i + 1]) {
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&row[start..i]);
+    }
+}
+
+/// Encodes an IFF ILBM: BMHD, CMAP, CAMG and a ByteRun1-compressed interleaved BODY.
+/// `pixels` holds one value per pixel, row-major; bit `k` goes to bitplane `k`.
+pub fn encode_ilbm(width: u32, height: u32, planes: u8, palette: &[[u8; 3]], camg: u32, pixels: &[u8]) -> Vec<u8> {
+    let (w, h) = (width as usize, height as usize);
+    let mut bmhd = Vec::with_capacity(20);
+    bmhd.extend_from_slice(&(width as u16).to_be_bytes());
+    bmhd.extend_from_slice(&(height as u16).to_be_bytes());
+    bmhd.extend_from_slice(&[0, 0, 0, 0]); // x, y
+    bmhd.push(planes);
+    bmhd.push(0); // masking: none
+    bmhd.push(1); // compression: ByteRun1
+    bmhd.push(0); // pad
+    bmhd.extend_from_slice(&0u16.to_be_bytes()); // transparent color
+    bmhd.extend_from_slice(&[1, 1]); // x/y aspect
+    bmhd.extend_from_slice(&(width as u16).to_be_bytes());
+    bmhd.extend_from_slice(&(height as u16).to_be_bytes());
+
+    let cmap: Vec<u8> = palette.iter().flatten().copied().collect();
+
+    // Rows are padded to whole 16-bit words
+    let row_bytes = w.div_ceil(16) * 2;
+    let mut body = Vec::new();
+    let mut row = vec![0u8; row_bytes];
+    for y in 0..h {
+        for plane in 0..planes {
+            row.fill(0);
+            for x in 0..w {
+                if (pixels[y * w + x] >> plane) & 1 == 1 {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            byte_run1(&row, &mut body);
+        }
+    }
+
+    let mut form = Vec::new();
+    form.extend_from_slice(b"ILBM");
+    push_chunk(&mut form, b"BMHD", &bmhd);
+    push_chunk(&mut form, b"CMAP", &cmap);
+    push_chunk(&mut form, b"CAMG", &camg.to_be_bytes());
+    push_chunk(&mut form, b"BODY", &body);
+    let mut out = Vec::with_capacity(form.len() + 8);
+    push_chunk(&mut out, b"FORM", &form);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ByteRun1 decoder: n in 0..=127 copies n+1 literals, n in 129..=255 repeats the next
+    // byte 257-n times, 128 is a no-op
+    fn unpack(mut data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        while let Some((&n, rest)) = data.split_first() {
+            match n {
+                0..=127 => {
+                    let len = n as usize + 1;
+                    out.extend_from_slice(&rest[..len]);
+                    data = &rest[len..];
+                }
+                128 => data = rest,
+                _ => {
+                    out.extend(std::iter::repeat_n(rest[0], 257 - n as usize));
+                    data = &rest[1..];
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn byte_run1_round_trips() {
+        let noisy: Vec<u8> = (0..300u32).map(|i| (i * 37 % 251) as u8).collect();
+        let rows: [Vec<u8>; 6] = [
+            vec![],
+            vec![7],
+            vec![0; 300],
+            noisy,
+            vec![1, 2, 2, 3, 3, 3, 4, 5, 6, 6],
+            [vec![9; 129], vec![1, 2, 3], vec![0; 2]].concat(),
+        ];
+        for row in rows {
+            let mut packed = Vec::new();
+            byte_run1(&row, &mut packed);
+            assert_eq!(unpack(&packed), row);
+        }
+        let mut packed = Vec::new();
+        byte_run1(&[5; 4], &mut packed);
+        assert_eq!(packed, [0xFD, 5]);
+    }
+
+    #[test]
+    fn ilbm_chunks_and_interleaved_body() {
+        // 3x2, two planes: values 0, 1, 2 on row 0, all 3 on row 1
+        let pixels = [0, 1, 2, 3, 3, 3];
+        let palette = [[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]];
+        let iff = encode_ilbm(3, 2, 2, &palette, CAMG_HAM, &pixels);
+        assert_eq!(&iff[..4], b"FORM");
+        assert_eq!(u32::from_be_bytes(iff[4..8].try_into().unwrap()) as usize, iff.len() - 8);
+        assert_eq!(&iff[8..12], b"ILBM");
+
+        // Walk the chunks after the form type
+        let mut chunks = std::collections::HashMap::new();
+        let mut at = 12;
+        while at < iff.len() {
+            let id: [u8; 4] = iff[at..at + 4].try_into().unwrap();
+            let len = u32::from_be_bytes(iff[at + 4..at + 8].try_into().unwrap()) as usize;
+            chunks.insert(id, iff[at + 8..at + 8 + len].to_vec());
+            at += 8 + len + len % 2;
+        }
+        let bmhd = &chunks[b"BMHD"];
+        assert_eq!(bmhd.len(), 20);
+        assert_eq!(bmhd[..4], [0, 3, 0, 2]);
+        assert_eq!((bmhd[8], bmhd[10]), (2, 1));
+        assert_eq!(chunks[b"CMAP"].len(), 12);
+        assert_eq!(chunks[b"CAMG"], CAMG_HAM.to_be_bytes());
+        // Rows are one word each, plane 0 then plane 1 per row
+        let body = unpack(&chunks[b"BODY"]);
+        assert_eq!(body, [0b0100_0000, 0, 0b0010_0000, 0, 0b1110_0000, 0, 0b1110_0000, 0]);
+    }
+}
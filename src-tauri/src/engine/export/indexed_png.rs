@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use crate::engine::algorithms::RgbaImage;
+use crate::engine::pipeline::EngineError;
+
+/// Builds the PLTE/tRNS table for an already-quantized image. Palette colors keep their
+/// order (offset by one when the transparent slot is enabled, which always takes index 0)
+/// so indices match the palette in pixel-art tools. Returns `None` past 256 entries.
+fn build_index(img: &RgbaImage, palette: &[[u8; 3]], transparent_slot: bool) -> Option<(Vec<[u8; 4]>, Vec<u8>)> {
+    fn push(entries: &mut Vec<[u8; 4]>, lookup: &mut HashMap<[u8; 4], u8>, c: [u8; 4]) -> Option<u8> {
+        if let Some(&i) = lookup.get(&c) { return Some(i); }
+        if entries.len() >= 256 { return None; }
+        let i = entries.len() as u8;
+        entries.push(c);
+        lookup.insert(c, i);
+        Some(i)
+    }
+    let mut entries: Vec<[u8; 4]> = Vec::new();
+    let mut lookup: HashMap<[u8; 4], u8> = HashMap::new();
+    if transparent_slot {
+        push(&mut entries, &mut lookup, [0, 0, 0, 0])?;
+    }
+    for c in palette {
+        push(&mut entries, &mut lookup, [c[0], c[1], c[2], 255])?;
+    }
+    let mut indices = Vec::with_capacity((img.width() * img.height()) as usize);
+    for p in img.pixels() {
+        let px = if transparent_slot && p.0[3] == 0 { [0, 0, 0, 0] } else { p.0 };
+        indices.push(push(&mut entries, &mut lookup, px)?);
+    }
+    Some((entries, indices))
+}
+
+/// Encodes a quantized grid as an 8-bit indexed PNG with tRNS for transparent entries.
+pub fn encode_indexed_png(img: &RgbaImage, palette: &[[u8; 3]], transparent_slot: bool) -> Result<Option<Vec<u8>>, EngineError> {
+    let Some((entries, indices)) = build_index(img, palette, transparent_slot) else {
+        return Ok(None);
+    };
+    let plte: Vec<u8> = entries.iter().flat_map(|e| [e[0], e[1], e[2]]).collect();
+    // tRNS only needs to run up to the last non-opaque entry
+    let trns_len = entries.iter().rposition(|e| e[3] != 255).map(|i| i + 1).unwrap_or(0);
+    let trns: Vec<u8> = entries[..trns_len].iter().map(|e| e[3]).collect();
+
+    let mut out = Vec::new();
+    {
+        let mut enc = png::Encoder::new(&mut out, img.width(), img.height());
+        enc.set_color(png::ColorType::Indexed);
+        enc.set_depth(png::BitDepth::Eight);
+        enc.set_palette(plte);
+        if !trns.is_empty() {
+            enc.set_trns(trns);
+        }
+        let mut writer = enc.write_header()?;
+        writer.write_image_data(&indices)?;
+    }
+    Ok(Some(out))
+}
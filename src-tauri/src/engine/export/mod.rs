@@ -0,0 +1,37 @@
+pub mod indexed_png;
+pub mod zx_scr;
+pub mod nes;
+pub mod c64;
+pub mod ilbm;
+pub mod gameboy;
+pub mod framebuffer;
+pub mod embedded;
+pub mod escpos;
+pub mod text;
+
+use std::fmt::Write;
+
+// Values per line in generated source arrays
+const VALUES_PER_LINE: usize = 16;
+
+/// Appends `<decl> <name>[N] = { ... };` to generated C source; `decl` is the qualified
+/// element type, e.g. `static const uint8_t` for arrays defined in a header.
+pub(crate) fn push_c_array(out: &mut String, decl: &str, name: &str, values: impl ExactSizeIterator<Item = String>) {
+    let _ = writeln!(out, "{decl} {name}[{}] = {{", values.len());
+    let values: Vec<String> = values.collect();
+    for line in values.chunks(VALUES_PER_LINE) {
+        let _ = writeln!(out, "    {},", line.join(", "));
+    }
+    out.push_str("};\n");
+}
+
+/// Appends `pub static <NAME>: [<ty>; N] = [ ... ];` to generated Rust source; a static keeps
+/// one copy of the array in flash instead of inlining it at every use.
+pub(crate) fn push_rust_array(out: &mut String, ty: &str, name: &str, values: impl ExactSizeIterator<Item = String>) {
+    let _ = writeln!(out, "pub static {name}: [{ty}; {}] = [", values.len());
+    let values: Vec<String> = values.collect();
+    for line in values.chunks(VALUES_PER_LINE) {
+        let _ = writeln!(out, "    {},", line.join(", "));
+    }
+    out.push_str("];\n");
+}
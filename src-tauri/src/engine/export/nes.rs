@@ -0,0 +1,102 @@
+use crate::engine::hardware::nes::{NesScreen, AREA, MAX_TILES, WIDTH};
+
+/// Encodes a 4 KiB pattern table (.chr): 16 bytes per tile, the low bit plane of all eight
+/// rows followed by the high bit plane, leftmost pixel in bit 7. Unused tiles are blank.
+pub fn encode_chr(screen: &NesScreen) -> Vec<u8> {
+    let mut out = vec![0u8; MAX_TILES * 16];
+    for (t, tile) in screen.tiles.iter().take(MAX_TILES).enumerate() {
+        for row in 0..8 {
+            let (mut lo, mut hi) = (0u8, 0u8);
+            for col in 0..8 {
+                let v = tile[row * 8 + col];
+                lo |= (v & 1) << (7 - col);
+                hi |= ((v >> 1) & 1) << (7 - col);
+            }
+            out[t * 16 + row] = lo;
+            out[t * 16 + 8 + row] = hi;
+        }
+    }
+    out
+}
+
+/// Encodes a nametable (.nam): 960 tile numbers, then the 64-byte attribute table. Each
+/// attribute byte covers 32x32 pixels, two bits per 16x16 area: top-left in bits 0-1,
+/// top-right 2-3, bottom-left 4-5, bottom-right 6-7.
+pub fn encode_nametable(screen: &NesScreen) -> Vec<u8> {
+    let areas_x = (WIDTH / AREA) as usize;
+    let areas_y = screen.areas.len() / areas_x;
+    let mut out = screen.nametable.clone();
+    for ay in 0..8 {
+        for ax in 0..8 {
+            let mut byte = 0u8;
+            for (quadrant, (qx, qy)) in [(0, 0), (1, 0), (0, 1), (1, 1)].into_iter().enumerate() {
+                let (x, y) = (ax * 2 + qx, ay * 2 + qy);
+                if y < areas_y {
+                    byte |= (screen.areas[y * areas_x + x] & 3) << (quadrant * 2);
+                }
+            }
+            out.push(byte);
+        }
+    }
+    out
+}
+
+/// Encodes the background palettes as 16 color numbers, ready for $3F00: each sub-palette is
+/// the backdrop followed by its three colors.
+pub fn encode_palette(screen: &NesScreen) -> Vec<u8> {
+    screen
+        .sub_palettes
+        .iter()
+        .flat_map(|sub| [screen.backdrop, sub[0], sub[1], sub[2]])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(tiles: Vec<[u8; 64]>) -> NesScreen {
+        NesScreen {
+            backdrop: 0x0F,
+            sub_palettes: [[0x01, 0x02, 0x03], [0x11, 0x12, 0x13], [0x21, 0x22, 0x23], [0x31, 0x32, 0x33]],
+            unique_tiles: tiles.len(),
+            tiles,
+            nametable: vec![0; 960],
+            areas: vec![0; 240],
+        }
+    }
+
+    #[test]
+    fn chr_writes_low_plane_rows_then_high_plane_rows() {
+        let mut tile = [0u8; 64];
+        tile[0] = 1; // row 0, leftmost: low plane only
+        tile[7] = 2; // row 0, rightmost: high plane only
+        tile[8 * 3 + 1] = 3; // row 3, second pixel: both planes
+        let chr = encode_chr(&screen(vec![[0; 64], tile]));
+        assert_eq!(chr.len(), 4096);
+        assert!(chr[..16].iter().all(|&b| b == 0));
+        let t = &chr[16..32];
+        assert_eq!(t[0], 0x80);
+        assert_eq!(t[8], 0x01);
+        assert_eq!((t[3], t[8 + 3]), (0x40, 0x40));
+        assert_eq!(t.iter().filter(|&&b| b != 0).count(), 4);
+    }
+
+    #[test]
+    fn nametable_packs_area_quadrants_into_attributes() {
+        let mut s = screen(vec![[0; 64]]);
+        s.nametable[5] = 7;
+        // First attribute byte: areas (0,0)=1, (1,0)=2, (0,1)=3, (1,1)=0
+        s.areas[0] = 1;
+        s.areas[1] = 2;
+        s.areas[16] = 3;
+        // Last row of areas (14) sits in the top half of the last attribute row
+        s.areas[14 * 16 + 15] = 2;
+        let nam = encode_nametable(&s);
+        assert_eq!(nam.len(), 1024);
+        assert_eq!(nam[5], 7);
+        assert_eq!(nam[960], 0b00_11_10_01);
+        assert_eq!(nam[1023], 0b00_00_10_00);
+        assert_eq!(encode_palette(&s)[4..8], [0x0F, 0x11, 0x12, 0x13]);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt::Write;
+
+use crate::engine::color::{lab_distance, rgb_to_lab};
+use crate::engine::text::{TextFormat, TextGrid};
+
+// xterm's default 16 colors; SGR 30..37 then 90..97
+const ANSI16: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00], [0xCD, 0x00, 0x00], [0x00, 0xCD, 0x00], [0xCD, 0xCD, 0x00],
+    [0x00, 0x00, 0xEE], [0xCD, 0x00, 0xCD], [0x00, 0xCD, 0xCD], [0xE5, 0xE5, 0xE5],
+    [0x7F, 0x7F, 0x7F], [0xFF, 0x00, 0x00], [0x00, 0xFF, 0x00], [0xFF, 0xFF, 0x00],
+    [0x5C, 0x5C, 0xFF], [0xFF, 0x00, 0xFF], [0x00, 0xFF, 0xFF], [0xFF, 0xFF, 0xFF],
+];
+// Channel levels of the xterm 6x6x6 color cube (indices 16..231)
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+const RESET: &str = "\x1b[0m";
+
+fn nearest(color: [u8; 3], candidates: impl Iterator<Item = (u8, [u8; 3])>) -> u8 {
+    let lab = rgb_to_lab(color[0], color[1], color[2]);
+    candidates
+        .map(|(i, c)| (i, lab_distance(lab, rgb_to_lab(c[0], c[1], c[2]))))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+// Foreground SGR parameters for a color
+fn sgr(format: TextFormat, c: [u8; 3]) -> String {
+    match format {
+        TextFormat::Ansi16 => {
+            let i = nearest(c, ANSI16.iter().enumerate().map(|(i, &c)| (i as u8, c)));
+            if i < 8 { format!("{}", 30 + i) } else { format!("{}", 90 + i - 8) }
+        }
+        TextFormat::Ansi256 => {
+            // Cube and gray ramp only; 0..15 are themeable and vary between terminals
+            let cube = (0..216u16).map(|i| {
+                let (r, g, b) = (i / 36, (i / 6) % 6, i % 6);
+                ((16 + i) as u8, [CUBE_LEVELS[r as usize], CUBE_LEVELS[g as usize], CUBE_LEVELS[b as usize]])
+            });
+            let grays = (0..24u8).map(|i| (232 + i, [8 + 10 * i; 3]));
+            format!("38;5;{}", nearest(c, cube.chain(grays)))
+        }
+        _ => format!("38;2;{};{};{}", c[0], c[1], c[2]),
+    }
+}
+
+fn html_escape(ch: char, out: &mut String) {
+    match ch {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        _ => out.push(ch),
+    }
+}
+
+/// Writes the grid as text, one line per row with trailing newlines. ANSI output only emits
+/// a color change where the next visible glyph needs one and resets at the end of every
+/// line, so it can be printed or `cat` to a terminal directly; plain ASCII trims trailing
+/// spaces. HTML wraps colored spans in a standalone `<pre>` block.
+pub fn encode_text(grid: &TextGrid, format: TextFormat) -> String {
+    let w = grid.columns as usize;
+    let mut out = String::new();
+    let (fg, bg) = if grid.dark_on_light { ("#000", "#fff") } else { ("#fff", "#000") };
+    if format == TextFormat::Html {
+        let _ = writeln!(out, "<pre style=\"font-family:monospace;line-height:1;color:{fg};background:{bg}\">");
+    }
+    for row in grid.cells.chunks(w.max(1)).take(grid.rows as usize) {
+        let mut line = String::new();
+        let mut current: Option<[u8; 3]> = None;
+        for cell in row {
+            let visible = cell.glyph != ' ';
+            match format {
+                TextFormat::Ascii => line.push(cell.glyph),
+                TextFormat::Html => {
+                    if visible && cell.color != current {
+                        if current.is_some() { line.push_str("</span>"); }
+                        if let Some(c) = cell.color {
+                            let _ = write!(line, "<span style=\"color:#{:02x}{:02x}{:02x}\">", c[0], c[1], c[2]);
+                        }
+                        current = cell.color;
+                    }
+                    html_escape(cell.glyph, &mut line);
+                }
+                _ => {
+                    if visible && cell.color != current {
+                        match cell.color {
+                            Some(c) => { let _ = write!(line, "\x1b[{}m", sgr(format, c)); }
+                            None => line.push_str(RESET),
+                        }
+                        current = cell.color;
+                    }
+                    line.push(cell.glyph);
+                }
+            }
+        }
+        match format {
+            TextFormat::Ascii => out.push_str(line.trim_end()),
+            TextFormat::Html => {
+                out.push_str(&line);
+                if current.is_some() { out.push_str("</span>"); }
+            }
+            _ => {
+                out.push_str(&line);
+                if current.is_some() { out.push_str(RESET); }
+            }
+        }
+        out.push('\n');
+    }
+    if format == TextFormat::Html {
+        out.push_str("</pre>\n");
+    }
+    out
+}
@@ -0,0 +1,71 @@
+use crate::engine::hardware::zx_spectrum::{ZxScreen, HEIGHT, WIDTH};
+
+/// Length of a .scr file: 6144 bitmap bytes followed by 768 attribute bytes
+pub const SCR_LEN: usize = 6912;
+const BITMAP_LEN: usize = 6144;
+
+// Display file offset of a pixel row: the screen is split into thirds of 64 rows, and within
+// a third the rows step through character rows before pixel lines
+fn row_offset(y: usize) -> usize {
+    ((y & 0xC0) << 5) | ((y & 0x07) << 8) | ((y & 0x38) << 2)
+}
+
+/// Encodes a raw ZX Spectrum screen dump (.scr) as laid out in memory at 0x4000: the bitmap in
+/// the display file's interleaved row order, leftmost pixel in bit 7 and set bits showing INK,
+/// then the attributes.
+pub fn encode_scr(screen: &ZxScreen) -> Vec<u8> {
+    let (w, h) = (WIDTH as usize, HEIGHT as usize);
+    let mut out = vec![0u8; SCR_LEN];
+    for y in 0..h {
+        let row = row_offset(y);
+        for col in 0..w / 8 {
+            let mut byte = 0u8;
+            for bit in 0..8 {
+                if screen.ink[y * w + col * 8 + bit] {
+                    byte |= 0x80 >> bit;
+                }
+            }
+            out[row + col] = byte;
+        }
+    }
+    out[BITMAP_LEN..].copy_from_slice(&screen.attributes);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blank_screen() -> ZxScreen {
+        ZxScreen { ink: vec![false; (WIDTH * HEIGHT) as usize], attributes: vec![0; 768] }
+    }
+
+    #[test]
+    fn writes_bitmap_then_attributes() {
+        let mut screen = blank_screen();
+        screen.attributes[0] = 0x47;
+        screen.attributes[767] = 0x38;
+        let scr = encode_scr(&screen);
+        assert_eq!(scr.len(), SCR_LEN);
+        assert_eq!(scr[BITMAP_LEN], 0x47);
+        assert_eq!(scr[SCR_LEN - 1], 0x38);
+    }
+
+    #[test]
+    fn interleaves_rows_by_third_line_and_character_row() {
+        // (row, byte offset of its first column in the display file)
+        let rows = [
+            (0, 0x0000), (1, 0x0100), (7, 0x0700), (8, 0x0020),
+            (9, 0x0120), (63, 0x07E0), (64, 0x0800), (191, 0x17E0),
+        ];
+        for (y, offset) in rows {
+            let mut screen = blank_screen();
+            screen.ink[y * WIDTH as usize] = true;
+            screen.ink[y * WIDTH as usize + 9] = true;
+            let scr = encode_scr(&screen);
+            assert_eq!(scr[offset], 0x80, "row {y}");
+            assert_eq!(scr[offset + 1], 0x40, "row {y}");
+            assert_eq!(scr[..BITMAP_LEN].iter().filter(|&&b| b != 0).count(), 2, "row {y}");
+        }
+    }
+}
@@ -3,6 +3,9 @@ use image::{imageops::FilterType, DynamicImage, ImageFormat, RgbaImage};
 use std::io::Cursor;
 use thiserror::Error;
 
+use crate::engine::decode::load_image_from_memory;
+use crate::engine::scalers::{upscale_pixel_art, PixelScaler};
+
 mod vhs;
 
 #[derive(Debug, Error)]
@@ -25,7 +28,7 @@ fn decode_data_url_to_image(data_url: &str) -> Result<DynamicImage, FilterError>
     let bytes = B64
         .decode(b64)
         .map_err(|_| FilterError::UnsupportedDataUrl)?;
-    let img = image::load_from_memory(&bytes)?;
+    let img = load_image_from_memory(&bytes)?;
     Ok(img)
 }
 
@@ -98,6 +101,15 @@ pub fn render_filters_preview_png(req: FilterChainRequest) -> Result<String, Fil
         }
     }
     let target = req.display_size.unwrap_or(560);
+    let scaler = req
+        .export_filter
+        .as_deref()
+        .and_then(|name| PixelScaler::from_name(name, req.export_scale_factor.unwrap_or(2)));
+    if let Some((scaler, factor)) = scaler {
+        // Only when the scaled frame still fits the display
+        let fit = target / frame.width().max(frame.height()).max(1);
+        if fit >= 2 { frame = upscale_pixel_art(&frame, scaler, factor.min(fit)); }
+    }
     let up = upscale_center_to(&frame, target);
     Ok(encode_png_base64(&up)?)
 }
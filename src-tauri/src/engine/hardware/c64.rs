@@ -0,0 +1,219 @@
+use super::{color_distance, dither_cells, flatten_alpha, segment_distance};
+use crate::engine::algorithms::RgbaImage;
+
+/// Hires screen width in pixels; multicolor pixels are double wide, giving half as many
+pub const WIDTH: u32 = 320;
+pub const HEIGHT: u32 = 200;
+/// Color cells per row and column (8x8 screen pixels each)
+pub const CELLS_X: u32 = 40;
+pub const CELLS_Y: u32 = 25;
+pub const BITMAP_LEN: usize = 8000;
+pub const CELL_COUNT: usize = 1000;
+
+/// VIC-II palette measurements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum C64Palette {
+    /// Philip "Pepto" Timmermann's PAL measurements
+    Pepto,
+    /// Colodore (Pepto's later revision)
+    Colodore,
+}
+
+impl C64Palette {
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name.map(|s| s.trim().to_ascii_lowercase()) {
+            Some(s) if s == "colodore" => Self::Colodore,
+            _ => Self::Pepto,
+        }
+    }
+
+    /// The 16 colors in VIC-II color number order.
+    pub fn colors(self) -> [[u8; 3]; 16] {
+        match self {
+            Self::Pepto => [
+                [0x00, 0x00, 0x00], [0xFF, 0xFF, 0xFF], [0x68, 0x37, 0x2B], [0x70, 0xA4, 0xB2],
+                [0x6F, 0x3D, 0x86], [0x58, 0x8D, 0x43], [0x35, 0x28, 0x79], [0xB8, 0xC7, 0x6F],
+                [0x6F, 0x4F, 0x25], [0x43, 0x39, 0x00], [0x9A, 0x67, 0x59], [0x44, 0x44, 0x44],
+                [0x6C, 0x6C, 0x6C], [0x9A, 0xD2, 0x84], [0x6C, 0x5E, 0xB5], [0x95, 0x95, 0x95],
+            ],
+            Self::Colodore => [
+                [0x00, 0x00, 0x00], [0xFF, 0xFF, 0xFF], [0x81, 0x33, 0x38], [0x75, 0xCE, 0xC8],
+                [0x8E, 0x3C, 0x97], [0x56, 0xAC, 0x4D], [0x2E, 0x2C, 0x9B], [0xED, 0xF1, 0x71],
+                [0x8E, 0x50, 0x29], [0x55, 0x38, 0x00], [0xC4, 0x6C, 0x71], [0x4A, 0x4A, 0x4A],
+                [0x7B, 0x7B, 0x7B], [0xA9, 0xFF, 0x9F], [0x70, 0x6D, 0xEB], [0xB2, 0xB2, 0xB2],
+            ],
+        }
+    }
+}
+
+/// A rendered bitmap screen in VIC-II memory layout.
+pub struct C64Bitmap {
+    pub multicolor: bool,
+    /// 8000 bytes in character-cell order: 8 consecutive bytes per cell, leftmost pixel in the
+    /// high bit(s)
+    pub bitmap: Vec<u8>,
+    /// 1000 screen RAM bytes. Hires: set bits use the high nibble, clear bits the low nibble.
+    /// Multicolor: %01 uses the high nibble, %10 the low nibble
+    pub screen: Vec<u8>,
+    /// 1000 color RAM nibbles (multicolor %11); zero in hires
+    pub color_ram: Vec<u8>,
+    /// Background color ($D021), multicolor %00
+    pub background: u8,
+}
+
+fn cell_pixels(img: &RgbaImage, x0: u32, y0: u32, w: u32) -> Vec<[f32; 3]> {
+    let mut out = Vec::with_capacity((w * 8) as usize);
+    for y in y0..(y0 + 8).min(img.height()) {
+        for x in x0..(x0 + w).min(img.width()) {
+            let p = img.get_pixel(x, y).0;
+            out.push([p[0] as f32, p[1] as f32, p[2] as f32]);
+        }
+    }
+    out
+}
+
+/// Hires: 320x200, each 8x8 cell uses the two palette colors whose blend line best covers
+/// its pixels; the screen is then dithered in one pass with the named algorithm, each pixel
+/// inside its cell's pair.
+pub fn render_hires(img: &mut RgbaImage, algorithm: &str, palette: C64Palette) -> C64Bitmap {
+    let colors = palette.colors();
+    let fcolors = colors.map(|c| c.map(|v| v as f32));
+    flatten_alpha(img, colors[0]);
+    let mut out = C64Bitmap {
+        multicolor: false,
+        bitmap: vec![0; BITMAP_LEN],
+        screen: vec![0; CELL_COUNT],
+        color_ram: vec![0; CELL_COUNT],
+        background: 0,
+    };
+    let mut pairs = Vec::with_capacity(CELL_COUNT);
+    for cy in 0..CELLS_Y {
+        for cx in 0..CELLS_X {
+            let pixels = cell_pixels(img, cx * 8, cy * 8, 8);
+            let mut best = (0u8, 0u8);
+            let mut best_cost = f32::INFINITY;
+            for a in 0..16u8 {
+                for b in a..16u8 {
+                    let (ca, cb) = (fcolors[a as usize], fcolors[b as usize]);
+                    let cost: f32 = pixels.iter().map(|&p| segment_distance(p, ca, cb)).sum();
+                    if cost < best_cost {
+                        best_cost = cost;
+                        best = (a, b);
+                    }
+                }
+            }
+            pairs.push(best);
+        }
+    }
+    let cell_palettes: Vec<Vec<[u8; 3]>> =
+        pairs.iter().map(|&(off, on)| vec![colors[off as usize], colors[on as usize]]).collect();
+    let bits = dither_cells(img, algorithm, (8, 8), &cell_palettes);
+    let width = img.width();
+    for (cell, &(off, on)) in pairs.iter().enumerate() {
+        let (x0, y0) = ((cell as u32 % CELLS_X) * 8, (cell as u32 / CELLS_X) * 8);
+        for row in 0..8 {
+            let mut byte = 0u8;
+            for col in 0..8 {
+                byte |= bits[((y0 + row) * width + x0 + col) as usize] << (7 - col);
+            }
+            out.bitmap[cell * 8 + row as usize] = byte;
+        }
+        out.screen[cell] = (on << 4) | off;
+    }
+    out
+}
+
+// Best three cell colors alongside `background`, each pixel paying its distance to the
+// closest of the four; greedy when `exhaustive` is false
+fn cell_colors(dists: &[[f32; 16]], background: usize, exhaustive: bool) -> [usize; 3] {
+    let base: Vec<f32> = dists.iter().map(|d| d[background]).collect();
+    let with = |mins: &[f32], c: usize| -> Vec<f32> { mins.iter().zip(dists).map(|(&m, d)| m.min(d[c])).collect() };
+    let error = |mins: &[f32], c: usize| -> f32 { mins.iter().zip(dists).map(|(&m, d)| m.min(d[c])).sum() };
+    if !exhaustive {
+        let mut mins = base;
+        let mut picks = [background; 3];
+        for pick in picks.iter_mut() {
+            *pick = (0..16).min_by(|&a, &b| error(&mins, a).total_cmp(&error(&mins, b))).unwrap_or(background);
+            mins = with(&mins, *pick);
+        }
+        return picks;
+    }
+    let mut best = [background; 3];
+    let mut best_err = f32::INFINITY;
+    for a in 0..16 {
+        let ma = with(&base, a);
+        for b in a..16 {
+            let mab = with(&ma, b);
+            for c in b..16 {
+                let e = error(&mab, c);
+                if e < best_err {
+                    best_err = e;
+                    best = [a, b, c];
+                }
+            }
+        }
+    }
+    best
+}
+
+// Total error of a cell under a background and three colors
+fn cell_error(dists: &[[f32; 16]], background: usize, colors: [usize; 3]) -> f32 {
+    dists
+        .iter()
+        .map(|d| colors.iter().fold(d[background], |m, &c| m.min(d[c])))
+        .sum()
+}
+
+/// Multicolor: 160x200 double-wide pixels. The shared background is the color that leaves
+/// the least error over all cells (each with greedy picks); each 4x8 cell then takes its best
+/// three further colors exhaustively, and the screen is dithered in one pass with each pixel
+/// inside its cell's four.
+pub fn render_multicolor(img: &mut RgbaImage, algorithm: &str, palette: C64Palette) -> C64Bitmap {
+    let colors = palette.colors();
+    let cell_dists: Vec<Vec<[f32; 16]>> = (0..CELLS_Y)
+        .flat_map(|cy| (0..CELLS_X).map(move |cx| (cx, cy)))
+        .map(|(cx, cy)| {
+            let opaque: Vec<[f32; 3]> = (cy * 8..(cy * 8 + 8).min(img.height()))
+                .flat_map(|y| (cx * 4..(cx * 4 + 4).min(img.width())).map(move |x| (x, y)))
+                .map(|(x, y)| img.get_pixel(x, y).0)
+                .filter(|p| p[3] >= 128)
+                .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+                .collect();
+            opaque
+                .iter()
+                .map(|&p| colors.map(|c| color_distance(p, c.map(|v| v as f32))))
+                .collect()
+        })
+        .collect();
+    let totals: Vec<f32> = (0..16)
+        .map(|bg| cell_dists.iter().map(|d| cell_error(d, bg, cell_colors(d, bg, false))).sum())
+        .collect();
+    let background = (0..16).min_by(|&a, &b| totals[a].total_cmp(&totals[b])).unwrap_or(0);
+
+    flatten_alpha(img, colors[background]);
+    let mut out = C64Bitmap {
+        multicolor: true,
+        bitmap: vec![0; BITMAP_LEN],
+        screen: vec![0; CELL_COUNT],
+        color_ram: vec![0; CELL_COUNT],
+        background: background as u8,
+    };
+    let picks: Vec<[usize; 3]> = cell_dists.iter().map(|d| cell_colors(d, background, true)).collect();
+    let cell_palettes: Vec<Vec<[u8; 3]>> =
+        picks.iter().map(|&[c1, c2, c3]| vec![colors[background], colors[c1], colors[c2], colors[c3]]).collect();
+    let values = dither_cells(img, algorithm, (4, 8), &cell_palettes);
+    let width = img.width();
+    for (cell, &[c1, c2, c3]) in picks.iter().enumerate() {
+        let (x0, y0) = ((cell as u32 % CELLS_X) * 4, (cell as u32 / CELLS_X) * 8);
+        for row in 0..8 {
+            let mut byte = 0u8;
+            for col in 0..4 {
+                byte |= values[((y0 + row) * width + x0 + col) as usize] << (6 - col * 2);
+            }
+            out.bitmap[cell * 8 + row as usize] = byte;
+        }
+        out.screen[cell] = ((c1 as u8) << 4) | c2 as u8;
+        out.color_ram[cell] = c3 as u8;
+    }
+    out
+}
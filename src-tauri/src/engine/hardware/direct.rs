@@ -0,0 +1,115 @@
+use image::Rgba;
+
+use super::flatten_alpha;
+use crate::engine::algorithms::RgbaImage;
+use crate::engine::color::brightness;
+use crate::engine::dither::channel::{apply_channel_dither_by_name, level_value};
+
+/// Framebuffer formats defined by channel bit depth rather than a color list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirectFormat {
+    /// 8 bits per pixel: RRRGGGBB
+    Rgb332,
+    /// 16 bits per pixel: 0000RRRR GGGGBBBB
+    Rgb444,
+    /// 15-bit color in 16 bits per pixel: 0RRRRRGG GGGBBBBB
+    Rgb555,
+    /// 16 bits per pixel: RRRRRGGG GGGBBBBB
+    Rgb565,
+    /// Grayscale with this many evenly spaced levels (2..=256), 0 being black
+    Gray(u16),
+}
+
+impl DirectFormat {
+    /// Parses "rgb332", "rgb444", "rgb555" ("rgb15"), "rgb565" ("rgb16") or "grayN"
+    /// ("greyN", "gray_N") for N levels.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rgb332" => Some(Self::Rgb332),
+            "rgb444" => Some(Self::Rgb444),
+            "rgb555" | "rgb15" => Some(Self::Rgb555),
+            "rgb565" | "rgb16" => Some(Self::Rgb565),
+            _ => {
+                let levels = name.strip_prefix("gray").or_else(|| name.strip_prefix("grey"))?;
+                let levels: u16 = levels.trim_start_matches('_').parse().ok()?;
+                (2..=256).contains(&levels).then_some(Self::Gray(levels))
+            }
+        }
+    }
+
+    /// Short name used in exported file names.
+    pub fn name(self) -> String {
+        match self {
+            Self::Rgb332 => "rgb332".into(),
+            Self::Rgb444 => "rgb444".into(),
+            Self::Rgb555 => "rgb555".into(),
+            Self::Rgb565 => "rgb565".into(),
+            Self::Gray(levels) => format!("gray{levels}"),
+        }
+    }
+
+    /// Bits per channel (R, G, B); grayscale uses the first entry.
+    pub fn channel_bits(self) -> [u8; 3] {
+        match self {
+            Self::Rgb332 => [3, 3, 2],
+            Self::Rgb444 => [4, 4, 4],
+            Self::Rgb555 => [5, 5, 5],
+            Self::Rgb565 => [5, 6, 5],
+            Self::Gray(_) => [self.bits_per_pixel(); 3],
+        }
+    }
+
+    /// Levels per channel (R, G, B).
+    pub fn levels(self) -> [u16; 3] {
+        match self {
+            Self::Gray(levels) => [levels; 3],
+            _ => self.channel_bits().map(|b| 1 << b),
+        }
+    }
+
+    /// Packed pixel size. Grayscale takes the smallest of 1, 2, 4 or 8 bits that holds
+    /// its levels, so pixels never straddle a byte.
+    pub fn bits_per_pixel(self) -> u8 {
+        match self {
+            Self::Rgb332 => 8,
+            Self::Rgb444 | Self::Rgb555 | Self::Rgb565 => 16,
+            Self::Gray(levels) => [1u8, 2, 4, 8].into_iter().find(|&b| (1u16 << b) >= levels).unwrap_or(8),
+        }
+    }
+
+    /// Every color the format can show, when there are at most 256 of them.
+    pub fn colors(self) -> Vec<[u8; 3]> {
+        let levels = self.levels();
+        match self {
+            Self::Gray(n) => (0..n).map(|v| [level_value(v, n); 3]).collect(),
+            Self::Rgb332 => (0..levels[0])
+                .flat_map(|r| (0..levels[1]).flat_map(move |g| (0..levels[2]).map(move |b| [r, g, b])))
+                .map(|q| [0, 1, 2].map(|ch| level_value(q[ch], levels[ch])))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A direct-color framebuffer before packing.
+pub struct DirectImage {
+    pub format: DirectFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Channel levels per pixel, row-major; grayscale repeats its level in all three
+    pub levels: Vec<[u16; 3]>,
+}
+
+/// Quantizes `img` to the format's channel depths, each channel dithered on its own with the
+/// named algorithm (grayscale converts to luma first). Transparency is flattened onto black.
+pub fn render_direct(img: &mut RgbaImage, algorithm: &str, format: DirectFormat) -> DirectImage {
+    flatten_alpha(img, [0, 0, 0]);
+    if let DirectFormat::Gray(_) = format {
+        for p in img.pixels_mut() {
+            let y = (brightness(p.0[0], p.0[1], p.0[2]) * 255.0).round() as u8;
+            *p = Rgba([y, y, y, 255]);
+        }
+    }
+    let levels = apply_channel_dither_by_name(img, algorithm, format.levels());
+    DirectImage { format, width: img.width(), height: img.height(), levels }
+}
@@ -0,0 +1,114 @@
+use super::{dither_block, flatten_alpha};
+use super::direct::{render_direct, DirectFormat};
+use crate::engine::algorithms::RgbaImage;
+
+/// Buffer layouts used by small embedded display controllers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayFormat {
+    /// 1bpp, 8 horizontal pixels per byte with the leftmost in bit 7, rows top to bottom
+    /// (Adafruit GFX bitmaps, most e-paper RAM)
+    MonoHorizontal,
+    /// 1bpp, 8 vertical pixels per byte with the top one in bit 7, each column top to bottom,
+    /// columns left to right
+    MonoVertical,
+    /// 1bpp in SSD1306/SH1106 page order: 8 vertical pixels per byte with the top one in bit 0,
+    /// left to right along each 8-row page, pages top to bottom
+    MonoPage,
+    /// 2bpp grayscale, 4 pixels per byte with the leftmost in the high bits, 0 black to 3 white
+    Gray2,
+    /// 16-bit RGB565 (ST7789/ILI9341), big-endian as sent over SPI, or little-endian
+    Rgb565 { big_endian: bool },
+    /// Three-color e-paper: a black/white plane (set bits white) and a red plane (set bits
+    /// red), both in horizontal 1bpp order
+    BlackWhiteRed,
+}
+
+impl DisplayFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mono_horizontal" | "1bpp_horizontal" | "mono" | "1bpp" => Some(Self::MonoHorizontal),
+            "mono_vertical" | "1bpp_vertical" => Some(Self::MonoVertical),
+            "mono_page" | "1bpp_page" | "ssd1306" => Some(Self::MonoPage),
+            "gray_2bpp" | "2bpp" => Some(Self::Gray2),
+            "rgb565_be" | "st7789" => Some(Self::Rgb565 { big_endian: true }),
+            "rgb565_le" => Some(Self::Rgb565 { big_endian: false }),
+            "bwr" | "epaper_bwr" => Some(Self::BlackWhiteRed),
+            _ => None,
+        }
+    }
+
+    /// Short description for generated source comments.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::MonoHorizontal => "1bpp, horizontal bytes, MSB left",
+            Self::MonoVertical => "1bpp, vertical bytes, MSB top, column-major",
+            Self::MonoPage => "1bpp, SSD1306 pages, LSB top",
+            Self::Gray2 => "2bpp grayscale, MSB left",
+            Self::Rgb565 { big_endian: true } => "RGB565, big-endian",
+            Self::Rgb565 { big_endian: false } => "RGB565, little-endian",
+            Self::BlackWhiteRed => "black/white and red planes, 1bpp horizontal, MSB left",
+        }
+    }
+
+    /// The colors the panel can show, in pixel value order; empty for RGB565.
+    pub fn palette(self) -> Vec<[u8; 3]> {
+        match self {
+            Self::MonoHorizontal | Self::MonoVertical | Self::MonoPage => vec![[0, 0, 0], [255, 255, 255]],
+            Self::Gray2 => vec![[0, 0, 0], [85, 85, 85], [170, 170, 170], [255, 255, 255]],
+            Self::Rgb565 { .. } => Vec::new(),
+            Self::BlackWhiteRed => vec![[255, 255, 255], [0, 0, 0], [255, 0, 0]],
+        }
+    }
+}
+
+/// How embedded display buffers are written out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceOutput {
+    /// C header with `const uint8_t` arrays (default)
+    C,
+    /// Rust module with `pub const` byte arrays
+    Rust,
+    /// Raw `.bin` buffers, one per plane
+    Bin,
+}
+
+impl SourceOutput {
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name.map(|s| s.trim().to_ascii_lowercase()) {
+            Some(s) if s == "rust" || s == "rs" => Self::Rust,
+            Some(s) if s == "bin" || s == "raw" => Self::Bin,
+            _ => Self::C,
+        }
+    }
+}
+
+/// A rendered display buffer before packing.
+pub struct DisplayImage {
+    pub format: DisplayFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Per pixel, row-major: the palette index, or the RGB565 word
+    pub pixels: Vec<u16>,
+}
+
+/// Dithers `img` to the panel's colors with the named algorithm (RGB565 quantizes each
+/// channel on its own). Transparency becomes the unlit color: black, or white on e-paper.
+pub fn render_display(img: &mut RgbaImage, algorithm: &str, format: DisplayFormat) -> DisplayImage {
+    let (width, height) = img.dimensions();
+    let pixels = match format {
+        DisplayFormat::Rgb565 { .. } => render_direct(img, algorithm, DirectFormat::Rgb565)
+            .levels
+            .into_iter()
+            .map(|[r, g, b]| (r << 11) | (g << 5) | b)
+            .collect(),
+        _ => {
+            let palette = format.palette();
+            flatten_alpha(img, palette[0]);
+            dither_block(img, (0, 0, width, height), algorithm, &palette)
+                .into_iter()
+                .map(u16::from)
+                .collect()
+        }
+    };
+    DisplayImage { format, width, height, pixels }
+}
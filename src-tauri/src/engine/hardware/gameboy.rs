@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use image::Rgba;
+
+use super::flatten_alpha;
+use crate::engine::algorithms::RgbaImage;
+use crate::engine::color::brightness;
+use crate::engine::dither::apply_dither_by_name;
+
+pub const TILE: u32 = 8;
+/// Tiles one background map can address
+pub const MAP_TILES: usize = 256;
+/// Tiles that fit in VRAM
+pub const VRAM_TILES: usize = 384;
+/// DMG green shades, lightest first; used when no palette is given
+pub const DMG_SHADES: [[u8; 3]; 4] = [[0xE0, 0xF8, 0xD0], [0x88, 0xC0, 0x70], [0x34, 0x68, 0x56], [0x08, 0x18, 0x20]];
+
+/// Four shades from `palette`, lightest first (shade 0 is the Game Boy's "white"). Larger
+/// palettes contribute the lightest, darkest and two evenly spaced colors by luminance;
+/// smaller ones repeat colors.
+pub fn shades_from_palette(palette: &[[u8; 3]]) -> [[u8; 3]; 4] {
+    if palette.is_empty() {
+        return DMG_SHADES;
+    }
+    let mut sorted = palette.to_vec();
+    sorted.sort_by(|a, b| brightness(b[0], b[1], b[2]).total_cmp(&brightness(a[0], a[1], a[2])));
+    let last = sorted.len() - 1;
+    [0, 1, 2, 3].map(|i| sorted[(i * last + 1) / 3])
+}
+
+/// A tile-mapped background.
+pub struct GbTiles {
+    pub shades: [[u8; 3]; 4],
+    /// Unique tiles as 64 shade numbers (0..3), row-major, in order of first use
+    pub tiles: Vec<[u8; 64]>,
+    /// Tile number per 8x8 cell, row-major
+    pub map: Vec<u16>,
+    pub map_width: u32,
+    pub map_height: u32,
+}
+
+/// Pads `img` to whole 8x8 tiles with shade 0, dithers it to the four shades with the named
+/// algorithm and splits it into deduplicated tiles and a tile map.
+pub fn render_gameboy(img: &mut RgbaImage, algorithm: &str, palette: &[[u8; 3]]) -> GbTiles {
+    let shades = shades_from_palette(palette);
+    let (w, h) = img.dimensions();
+    let (map_width, map_height) = (w.div_ceil(TILE), h.div_ceil(TILE));
+    let [r, g, b] = shades[0];
+    let mut padded = RgbaImage::from_pixel(map_width * TILE, map_height * TILE, Rgba([r, g, b, 255]));
+    flatten_alpha(img, shades[0]);
+    image::imageops::replace(&mut padded, img, 0, 0);
+    apply_dither_by_name(&mut padded, algorithm, &shades);
+
+    // Snap to shade numbers and write the exact shades back
+    let mut values = vec![0u8; (padded.width() * padded.height()) as usize];
+    for (v, p) in values.iter_mut().zip(padded.pixels_mut()) {
+        let d = |c: &[u8; 3]| (0..3).map(|k| (p.0[k] as i32 - c[k] as i32).pow(2)).sum::<i32>();
+        *v = (0..4).min_by_key(|&i| d(&shades[i])).unwrap_or(0) as u8;
+        let [r, g, b] = shades[*v as usize];
+        *p = Rgba([r, g, b, 255]);
+    }
+    *img = padded;
+
+    let stride = (map_width * TILE) as usize;
+    let mut out = GbTiles { shades, tiles: Vec::new(), map: Vec::new(), map_width, map_height };
+    let mut index: HashMap<[u8; 64], u16> = HashMap::new();
+    for ty in 0..map_height as usize {
+        for tx in 0..map_width as usize {
+            let mut tile = [0u8; 64];
+            for (k, v) in tile.iter_mut().enumerate() {
+                *v = values[(ty * 8 + k / 8) * stride + tx * 8 + k % 8];
+            }
+            let next = out.tiles.len() as u16;
+            let i = *index.entry(tile).or_insert(next);
+            if i == next {
+                out.tiles.push(tile);
+            }
+            out.map.push(i);
+        }
+    }
+    out
+}
+
+/// Warnings for tile counts past what the hardware can show.
+pub fn tile_warnings(tiles: &GbTiles) -> Vec<String> {
+    let n = tiles.tiles.len();
+    let mut warnings = Vec::new();
+    if n > MAP_TILES {
+        warnings.push(format!(
+            "{n} unique tiles exceed the {MAP_TILES} one background map can address; the tile map is written as 16-bit little-endian entries (tilemap_16le.bin)"
+        ));
+    }
+    if n > VRAM_TILES {
+        warnings.push(format!("{n} unique tiles exceed the {VRAM_TILES} that fit in VRAM"));
+    }
+    warnings
+}
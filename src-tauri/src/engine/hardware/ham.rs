@@ -0,0 +1,181 @@
+use image::Rgba;
+
+use super::{color_distance, flatten_alpha};
+use crate::engine::algorithms::RgbaImage;
+
+// k-means rounds for the base palette
+const KMEANS_ITERATIONS: usize = 12;
+// Pixels sampled for the base palette fit
+const KMEANS_SAMPLES: usize = 20_000;
+
+/// Hold-And-Modify variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HamDepth {
+    /// OCS/ECS: 6 bitplanes, 16 base colors, 4-bit channel modifies (12-bit color)
+    Ham6,
+    /// AGA: 8 bitplanes, 64 base colors, 6-bit channel modifies keeping the low 2 bits
+    Ham8,
+}
+
+impl HamDepth {
+    pub fn planes(self) -> u8 {
+        match self {
+            Self::Ham6 => 6,
+            Self::Ham8 => 8,
+        }
+    }
+
+    fn data_bits(self) -> u8 {
+        self.planes() - 2
+    }
+
+    pub fn base_colors(self) -> usize {
+        1 << self.data_bits()
+    }
+
+    // Palette precision: HAM6 base colors are 12-bit, HAM8 base colors 24-bit
+    fn snap_base(self, c: [f32; 3]) -> [u8; 3] {
+        match self {
+            Self::Ham6 => c.map(|v| ((v / 17.0).round().clamp(0.0, 15.0) as u8) * 17),
+            Self::Ham8 => c.map(|v| v.round().clamp(0.0, 255.0) as u8),
+        }
+    }
+
+    // Data value and resulting channel for a modify toward `target` from `prev`
+    fn modify(self, prev: u8, target: u8) -> (u8, u8) {
+        match self {
+            Self::Ham6 => {
+                let v = (target as f32 / 17.0).round() as u8;
+                (v, v * 17)
+            }
+            Self::Ham8 => {
+                let low = prev & 3;
+                let v = ((target as f32 - low as f32) / 4.0).round().clamp(0.0, 63.0) as u8;
+                (v, (v << 2) | low)
+            }
+        }
+    }
+}
+
+/// An encoded HAM picture.
+pub struct HamImage {
+    pub depth: HamDepth,
+    pub width: u32,
+    pub height: u32,
+    /// Base palette (16 or 64 entries)
+    pub palette: Vec<[u8; 3]>,
+    /// One raw pixel value per pixel, row-major: the top two bits select set-from-palette (00),
+    /// modify blue (01), red (10) or green (11); the rest is the index or channel value
+    pub pixels: Vec<u8>,
+}
+
+// Deterministic k-means: farthest-point seeding, then Lloyd iterations
+fn kmeans(samples: &[[f32; 3]], k: usize) -> Vec<[f32; 3]> {
+    if samples.is_empty() {
+        return vec![[0.0; 3]; k];
+    }
+    let mut centers = vec![samples[0]];
+    let mut nearest: Vec<f32> = samples.iter().map(|&s| color_distance(s, centers[0])).collect();
+    while centers.len() < k {
+        let (far, _) = nearest
+            .iter()
+            .enumerate()
+            .fold((0, -1.0f32), |best, (i, &d)| if d > best.1 { (i, d) } else { best });
+        let c = samples[far];
+        centers.push(c);
+        for (n, &s) in nearest.iter_mut().zip(samples) {
+            *n = n.min(color_distance(s, c));
+        }
+    }
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![[0.0f32; 4]; k];
+        for &s in samples {
+            let i = (0..k)
+                .min_by(|&a, &b| color_distance(s, centers[a]).total_cmp(&color_distance(s, centers[b])))
+                .unwrap_or(0);
+            for ch in 0..3 {
+                sums[i][ch] += s[ch];
+            }
+            sums[i][3] += 1.0;
+        }
+        for (c, sum) in centers.iter_mut().zip(&sums) {
+            if sum[3] > 0.0 {
+                *c = [sum[0] / sum[3], sum[1] / sum[3], sum[2] / sum[3]];
+            }
+        }
+    }
+    centers
+}
+
+// Pixel value and resulting color for each choice at one pixel: every base color, then a
+// modify of each channel toward the target
+fn choices(depth: HamDepth, palette: &[[u8; 3]], prev: [u8; 3], target: [u8; 3]) -> Vec<(u8, [u8; 3])> {
+    let data_bits = depth.data_bits();
+    let mut out: Vec<(u8, [u8; 3])> = palette.iter().enumerate().map(|(i, &c)| (i as u8, c)).collect();
+    // Control codes: 01 blue, 10 red, 11 green
+    for (control, ch) in [(1u8, 2usize), (2, 0), (3, 1)] {
+        let (v, channel) = depth.modify(prev[ch], target[ch]);
+        let mut c = prev;
+        c[ch] = channel;
+        out.push(((control << data_bits) | v, c));
+    }
+    out
+}
+
+fn error(a: [u8; 3], b: [u8; 3]) -> f32 {
+    color_distance(a.map(|v| v as f32), b.map(|v| v as f32))
+}
+
+/// Encodes `img` as HAM: k-means picks the base palette, then each scanline is encoded left
+/// to right, choosing per pixel between the base colors and the three channel modifies by
+/// its own error plus the best error reachable at the next pixel (one-pixel lookahead).
+/// Lines start from color 0 as on hardware. `img` is rewritten to the decoded result, so
+/// the preview shows the characteristic horizontal fringing.
+pub fn render_ham(img: &mut RgbaImage, depth: HamDepth) -> HamImage {
+    flatten_alpha(img, [0, 0, 0]);
+    let (w, h) = img.dimensions();
+    let step = ((w as usize * h as usize) / KMEANS_SAMPLES).max(1);
+    let samples: Vec<[f32; 3]> = img
+        .pixels()
+        .step_by(step)
+        .map(|p| [p.0[0] as f32, p.0[1] as f32, p.0[2] as f32])
+        .collect();
+    let mut palette: Vec<[u8; 3]> = kmeans(&samples, depth.base_colors()).into_iter().map(|c| depth.snap_base(c)).collect();
+    // Darkest first: color 0 is also the border and the start of every line
+    palette.sort_by_key(|c| c.iter().map(|&v| v as u32).sum::<u32>());
+
+    let mut pixels = vec![0u8; (w * h) as usize];
+    for y in 0..h {
+        let targets: Vec<[u8; 3]> = (0..w).map(|x| {
+            let p = img.get_pixel(x, y).0;
+            [p[0], p[1], p[2]]
+        }).collect();
+        // Best base color per pixel does not depend on the previous pixel
+        let base_error: Vec<f32> = targets
+            .iter()
+            .map(|&t| palette.iter().map(|&c| error(c, t)).fold(f32::INFINITY, f32::min))
+            .collect();
+        let mut prev = palette[0];
+        for x in 0..w as usize {
+            let t = targets[x];
+            let mut best = (f32::INFINITY, 0u8, prev);
+            for (code, c) in choices(depth, &palette, prev, t) {
+                let mut cost = error(c, t);
+                if let Some(&next) = targets.get(x + 1) {
+                    let modify_next = choices(depth, &[], c, next)
+                        .into_iter()
+                        .map(|(_, n)| error(n, next))
+                        .fold(f32::INFINITY, f32::min);
+                    cost += base_error[x + 1].min(modify_next);
+                }
+                if cost < best.0 {
+                    best = (cost, code, c);
+                }
+            }
+            pixels[y as usize * w as usize + x] = best.1;
+            prev = best.2;
+            img.put_pixel(x as u32, y, Rgba([prev[0], prev[1], prev[2], 255]));
+        }
+    }
+    HamImage { depth, width: w, height: h, palette, pixels }
+}
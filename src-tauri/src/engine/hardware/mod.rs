@@ -0,0 +1,409 @@
+pub mod c64;
+pub mod direct;
+pub mod display;
+pub mod gameboy;
+pub mod ham;
+pub mod nes;
+pub mod thermal;
+pub mod zx_spectrum;
+
+use image::Rgba;
+
+use super::algorithms::RgbaImage;
+use super::dither::apply_dither_by_name;
+use super::dither::bayer::{BAYER_2X2, BAYER_4X4, BAYER_8X8};
+use super::dither::channel::{kernel_by_name, Kernel};
+use super::export::c64::{encode_advanced_art_studio, encode_art_studio, encode_koala};
+use super::export::embedded::{display_c_source, display_rust_source, encode_display_planes};
+use super::export::escpos::encode_escpos_raster;
+use super::export::framebuffer::encode_framebuffer;
+use super::export::gameboy::{encode_2bpp, encode_tilemap, tilemap_c_source, wide_map};
+use super::export::ilbm::{encode_ilbm, CAMG_HAM};
+use super::export::nes::{encode_chr, encode_nametable, encode_palette};
+use super::export::zx_scr::encode_scr;
+
+// Channel weights for the pair searches (rough perceptual RGB)
+const WEIGHTS: [f32; 3] = [3.0, 4.0, 2.0];
+
+/// Fixed-hardware render targets. Each forces its screen resolution and palette and
+/// enforces the machine's color constraints during quantization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HardwareMode {
+    /// ZX Spectrum: 256x192, one INK and one PAPER per 8x8 attribute cell
+    ZxSpectrum,
+    /// NES background: 256x240, backdrop plus four 3-color sub-palettes, one per 16x16 area
+    Nes,
+    /// C64 hires bitmap: 320x200, two colors per 8x8 cell
+    C64Hires(c64::C64Palette),
+    /// C64 multicolor bitmap: 160x200 double-wide pixels, shared background plus three
+    /// colors per 4x8 cell
+    C64Multicolor(c64::C64Palette),
+    /// Amiga Hold-And-Modify at the requested grid size
+    Ham(ham::HamDepth),
+    /// Game Boy background: the requested grid padded to 8x8 tiles, four shades from the
+    /// selected palette
+    GameBoy,
+    /// Direct color at the requested grid size: each channel quantized to its bit depth on
+    /// its own, without a palette
+    Direct(direct::DirectFormat),
+    /// Embedded display buffer at the requested grid size, in the panel's colors and byte
+    /// layout, written as C or Rust source or raw buffers
+    Display(display::DisplayFormat, display::SourceOutput),
+    /// ESC/POS thermal printer: this many dots wide, height following the source, 1-bit
+    Thermal(u32),
+}
+
+impl HardwareMode {
+    /// Parses the mode name; `palette` picks a palette variant where the hardware has
+    /// several (C64: "pepto" or "colodore") and `output` how display buffers are written.
+    /// Direct-color, display and printer formats are named as in
+    /// [`direct::DirectFormat::from_name`], [`display::DisplayFormat::from_name`] and
+    /// [`thermal::dots_from_name`].
+    pub fn from_request(name: Option<&str>, palette: Option<&str>, output: Option<&str>) -> Option<Self> {
+        let n = name?.trim().to_ascii_lowercase().replace(['-', ' '], "_");
+        match n.as_str() {
+            "zx_spectrum" | "zxspectrum" | "spectrum" | "zx" => Some(Self::ZxSpectrum),
+            "nes" | "famicom" => Some(Self::Nes),
+            "c64_hires" | "c64" => Some(Self::C64Hires(c64::C64Palette::from_name(palette))),
+            "c64_multicolor" | "c64_mc" => Some(Self::C64Multicolor(c64::C64Palette::from_name(palette))),
+            "ham6" | "ham" => Some(Self::Ham(ham::HamDepth::Ham6)),
+            "ham8" => Some(Self::Ham(ham::HamDepth::Ham8)),
+            "gameboy" | "game_boy" | "gb" | "dmg" => Some(Self::GameBoy),
+            _ => direct::DirectFormat::from_name(&n).map(Self::Direct).or_else(|| {
+                display::DisplayFormat::from_name(&n).map(|f| Self::Display(f, display::SourceOutput::from_name(output)))
+            }).or_else(|| thermal::dots_from_name(&n).map(Self::Thermal)),
+        }
+    }
+
+    /// Logical screen size in pixels for a `source` of the given size; None keeps the
+    /// requested grid.
+    pub fn grid_size(self, source: (u32, u32)) -> Option<(u32, u32)> {
+        match self {
+            Self::ZxSpectrum => Some((zx_spectrum::WIDTH, zx_spectrum::HEIGHT)),
+            Self::Nes => Some((nes::WIDTH, nes::HEIGHT)),
+            Self::C64Hires(_) => Some((c64::WIDTH, c64::HEIGHT)),
+            Self::C64Multicolor(_) => Some((c64::WIDTH / 2, c64::HEIGHT)),
+            Self::Thermal(dots) => Some((dots, thermal::print_height(dots, source))),
+            Self::Ham(_) | Self::GameBoy | Self::Direct(_) | Self::Display(..) => None,
+        }
+    }
+
+    /// Pixel width / height on the original display; None keeps the requested aspect.
+    pub fn pixel_aspect(self) -> Option<f32> {
+        match self {
+            Self::ZxSpectrum | Self::Nes | Self::C64Hires(_) => Some(1.0),
+            Self::C64Multicolor(_) => Some(2.0),
+            Self::Ham(_) | Self::Direct(_) => None,
+            Self::GameBoy | Self::Display(..) | Self::Thermal(_) => Some(1.0),
+        }
+    }
+}
+
+/// A native file produced by a hardware mode.
+pub struct HardwareFile {
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// What a hardware render produced besides the image: the colors in use, native files and
+/// warnings about hardware limits the result exceeds.
+pub struct HardwareFrame {
+    pub palette: Vec<[u8; 3]>,
+    pub files: Vec<HardwareFile>,
+    pub warnings: Vec<String>,
+}
+
+/// Quantizes `img` (already at the mode's grid size) under the mode's constraints, dithering
+/// with the named algorithm where the mode dithers (HAM encodes directly), and encodes the
+/// native files. `palette` is the selected palette, used by modes without a fixed one.
+pub fn render_hardware(mode: HardwareMode, img: &mut RgbaImage, algorithm: &str, palette: &[[u8; 3]]) -> HardwareFrame {
+    match mode {
+        HardwareMode::ZxSpectrum => {
+            let screen = zx_spectrum::render_zx_spectrum(img, algorithm);
+            HardwareFrame {
+                palette: zx_spectrum::spectrum_palette(),
+                files: vec![HardwareFile { file_name: "screen.scr".into(), bytes: encode_scr(&screen) }],
+                warnings: Vec::new(),
+            }
+        }
+        HardwareMode::Nes => {
+            let screen = nes::render_nes(img, algorithm);
+            let mut palette: Vec<[u8; 3]> = Vec::new();
+            for number in encode_palette(&screen) {
+                let c = nes::NES_PALETTE[number as usize];
+                if !palette.contains(&c) { palette.push(c); }
+            }
+            HardwareFrame {
+                palette,
+                files: vec![
+                    HardwareFile { file_name: "tiles.chr".into(), bytes: encode_chr(&screen) },
+                    HardwareFile { file_name: "screen.nam".into(), bytes: encode_nametable(&screen) },
+                    HardwareFile { file_name: "palette.pal".into(), bytes: encode_palette(&screen) },
+                ],
+                warnings: nes::tile_warnings(&screen),
+            }
+        }
+        HardwareMode::C64Hires(palette) => {
+            let bitmap = c64::render_hires(img, algorithm, palette);
+            HardwareFrame {
+                palette: palette.colors().to_vec(),
+                files: vec![HardwareFile { file_name: "picture.art".into(), bytes: encode_art_studio(&bitmap) }],
+                warnings: Vec::new(),
+            }
+        }
+        HardwareMode::C64Multicolor(palette) => {
+            let bitmap = c64::render_multicolor(img, algorithm, palette);
+            HardwareFrame {
+                palette: palette.colors().to_vec(),
+                files: vec![
+                    HardwareFile { file_name: "picture.koa".into(), bytes: encode_koala(&bitmap) },
+                    HardwareFile { file_name: "picture.ocp".into(), bytes: encode_advanced_art_studio(&bitmap) },
+                ],
+                warnings: Vec::new(),
+            }
+        }
+        HardwareMode::GameBoy => {
+            let tiles = gameboy::render_gameboy(img, algorithm, palette);
+            HardwareFrame {
+                palette: tiles.shades.to_vec(),
+                files: vec![
+                    HardwareFile { file_name: "tiles.2bpp".into(), bytes: encode_2bpp(&tiles) },
+                    HardwareFile {
+                        // 16-bit entries are not what map loaders expect; say so in the name
+                        file_name: if wide_map(&tiles) { "tilemap_16le.bin".into() } else { "tilemap.bin".into() },
+                        bytes: encode_tilemap(&tiles),
+                    },
+                    HardwareFile { file_name: "background.c".into(), bytes: tilemap_c_source(&tiles, "background").into_bytes() },
+                ],
+                warnings: gameboy::tile_warnings(&tiles),
+            }
+        }
+        HardwareMode::Ham(depth) => {
+            let ham = ham::render_ham(img, depth);
+            let bytes = encode_ilbm(ham.width, ham.height, depth.planes(), &ham.palette, CAMG_HAM, &ham.pixels);
+            HardwareFrame {
+                palette: ham.palette,
+                files: vec![HardwareFile { file_name: "picture.iff".into(), bytes }],
+                warnings: Vec::new(),
+            }
+        }
+        HardwareMode::Direct(format) => {
+            let direct = direct::render_direct(img, algorithm, format);
+            HardwareFrame {
+                palette: format.colors(),
+                files: vec![HardwareFile {
+                    file_name: format!("framebuffer_{}.bin", format.name()),
+                    bytes: encode_framebuffer(&direct),
+                }],
+                warnings: Vec::new(),
+            }
+        }
+        HardwareMode::Display(format, output) => {
+            let buffer = display::render_display(img, algorithm, format);
+            let files = match output {
+                display::SourceOutput::C => vec![HardwareFile {
+                    file_name: "bitmap.h".into(),
+                    bytes: display_c_source(&buffer, "bitmap").into_bytes(),
+                }],
+                display::SourceOutput::Rust => vec![HardwareFile {
+                    file_name: "bitmap.rs".into(),
+                    bytes: display_rust_source(&buffer, "bitmap").into_bytes(),
+                }],
+                display::SourceOutput::Bin => encode_display_planes(&buffer)
+                    .into_iter()
+                    .map(|(plane, bytes)| HardwareFile {
+                        file_name: if plane.is_empty() { "bitmap.bin".into() } else { format!("bitmap_{plane}.bin") },
+                        bytes,
+                    })
+                    .collect(),
+            };
+            HardwareFrame { palette: format.palette(), files, warnings: Vec::new() }
+        }
+        HardwareMode::Thermal(_) => {
+            let dots = thermal::render_thermal(img, algorithm);
+            HardwareFrame {
+                palette: thermal::PAPER_INK.to_vec(),
+                files: vec![HardwareFile {
+                    file_name: "receipt.bin".into(),
+                    bytes: encode_escpos_raster(img.width(), img.height(), &dots),
+                }],
+                warnings: Vec::new(),
+            }
+        }
+    }
+}
+
+/// Composites every pixel over `background` and makes it opaque; hardware screens have no alpha.
+pub(crate) fn flatten_alpha(img: &mut RgbaImage, background: [u8; 3]) {
+    for p in img.pixels_mut() {
+        let a = p.0[3] as u16;
+        for (ch, &bg) in background.iter().enumerate() {
+            p.0[ch] = ((p.0[ch] as u16 * a + bg as u16 * (255 - a)) / 255) as u8;
+        }
+        p.0[3] = 255;
+    }
+}
+
+/// Dithers the block `(x, y, w, h)` with `algorithm` restricted to `palette`, writes the exact
+/// palette colors back and returns each pixel's palette index, row-major.
+pub(crate) fn dither_block(
+    img: &mut RgbaImage,
+    (x, y, w, h): (u32, u32, u32, u32),
+    algorithm: &str,
+    palette: &[[u8; 3]],
+) -> Vec<u8> {
+    let mut block = image::imageops::crop_imm(img, x, y, w, h).to_image();
+    apply_dither_by_name(&mut block, algorithm, palette);
+    // Snap whatever the algorithm produced onto the palette
+    let nearest = |p: &Rgba<u8>| {
+        let d = |c: &[u8; 3]| (0..3).map(|k| (p.0[k] as i32 - c[k] as i32).pow(2)).sum::<i32>();
+        (0..palette.len()).min_by_key(|&i| d(&palette[i])).unwrap_or(0) as u8
+    };
+    let indices: Vec<u8> = block.pixels().map(nearest).collect();
+    for (k, &i) in indices.iter().enumerate() {
+        let c = palette[i as usize];
+        img.put_pixel(x + k as u32 % w, y + k as u32 / w, Rgba([c[0], c[1], c[2], 255]));
+    }
+    indices
+}
+
+/// Dithers all of `img` in one pass with `algorithm`, each pixel choosing only among the
+/// colors of its own `(w, h)` cell: `palettes` holds one palette per cell, row-major. Error
+/// diffusion carries across cell edges (Edge Dithering diffuses with the Floyd-Steinberg
+/// kernel here) and Bayer thresholds between the cell's two colors closest to the pixel;
+/// algorithms that don't spread error run per cell through [`dither_block`]. Writes the
+/// colors back and returns each pixel's index into its cell palette, row-major.
+pub(crate) fn dither_cells(
+    img: &mut RgbaImage,
+    algorithm: &str,
+    (cw, ch): (u32, u32),
+    palettes: &[Vec<[u8; 3]>],
+) -> Vec<u8> {
+    let (w, h) = img.dimensions();
+    let cells_x = w.div_ceil(cw);
+    let palette_at = |x: u32, y: u32| palettes[((y / ch) * cells_x + x / cw) as usize].as_slice();
+    let indices = match algorithm {
+        "Bayer" => ordered_cells(img, &palette_at, &BAYER_4X4),
+        "Bayer 2x2" => ordered_cells(img, &palette_at, &BAYER_2X2),
+        "Bayer 8x8" => ordered_cells(img, &palette_at, &BAYER_8X8),
+        "Edge Dithering" => diffuse_cells(img, &palette_at, kernel_by_name("Floyd-Steinberg").unwrap_or((&[], 1.0))),
+        _ => match kernel_by_name(algorithm) {
+            Some(kernel) => diffuse_cells(img, &palette_at, kernel),
+            None => {
+                let mut indices = vec![0u8; (w * h) as usize];
+                for cy in (0..h).step_by(ch as usize) {
+                    for cx in (0..w).step_by(cw as usize) {
+                        let (bw, bh) = (cw.min(w - cx), ch.min(h - cy));
+                        let block = dither_block(img, (cx, cy, bw, bh), algorithm, palette_at(cx, cy));
+                        for (k, &i) in block.iter().enumerate() {
+                            let (x, y) = (cx + k as u32 % bw, cy + k as u32 / bw);
+                            indices[(y * w + x) as usize] = i;
+                        }
+                    }
+                }
+                return indices;
+            }
+        },
+    };
+    for (k, &i) in indices.iter().enumerate() {
+        let (x, y) = (k as u32 % w, k as u32 / w);
+        let c = palette_at(x, y)[i as usize];
+        img.put_pixel(x, y, Rgba([c[0], c[1], c[2], 255]));
+    }
+    indices
+}
+
+fn to_f32(c: [u8; 3]) -> [f32; 3] {
+    c.map(|v| v as f32)
+}
+
+fn nearest_in(p: [f32; 3], palette: &[[u8; 3]]) -> u8 {
+    (0..palette.len())
+        .min_by(|&i, &j| color_distance(p, to_f32(palette[i])).total_cmp(&color_distance(p, to_f32(palette[j]))))
+        .unwrap_or(0) as u8
+}
+
+// Serpentine error diffusion over the whole image, quantizing each pixel to its cell palette
+fn diffuse_cells<'a>(
+    img: &RgbaImage,
+    palette_at: &impl Fn(u32, u32) -> &'a [[u8; 3]],
+    (kernel, divisor): Kernel,
+) -> Vec<u8> {
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    let mut buf: Vec<[f32; 3]> = img.pixels().map(|p| [p.0[0] as f32, p.0[1] as f32, p.0[2] as f32]).collect();
+    let mut out = vec![0u8; buf.len()];
+    for y in 0..h {
+        let ltr = y % 2 == 0;
+        for i in 0..w {
+            let x = if ltr { i } else { w - 1 - i };
+            let idx = (y * w + x) as usize;
+            let palette = palette_at(x as u32, y as u32);
+            let v = buf[idx];
+            let q = nearest_in(v, palette);
+            out[idx] = q;
+            let c = to_f32(palette[q as usize]);
+            let err = [0, 1, 2].map(|k| v[k] - c[k]);
+            for &(dx, dy, weight) in kernel {
+                let nx = if ltr { x + dx } else { x - dx };
+                let ny = y + dy;
+                if nx < 0 || nx >= w || ny >= h { continue; }
+                let n = &mut buf[(ny * w + nx) as usize];
+                for k in 0..3 {
+                    n[k] = (n[k] + err[k] * weight / divisor).clamp(0.0, 255.0);
+                }
+            }
+        }
+    }
+    out
+}
+
+// Ordered dither between the two cell colors nearest each pixel: the pixel's position along
+// the segment between them is compared against the matrix threshold
+fn ordered_cells<'a, const N: usize>(
+    img: &RgbaImage,
+    palette_at: &impl Fn(u32, u32) -> &'a [[u8; 3]],
+    matrix: &[[u8; N]; N],
+) -> Vec<u8> {
+    let denom = (N * N) as f32;
+    img.enumerate_pixels()
+        .map(|(x, y, p)| {
+            let p = [p.0[0] as f32, p.0[1] as f32, p.0[2] as f32];
+            let palette = palette_at(x, y);
+            let mut order: Vec<usize> = (0..palette.len()).collect();
+            order.sort_by(|&i, &j| color_distance(p, to_f32(palette[i])).total_cmp(&color_distance(p, to_f32(palette[j]))));
+            let (Some(&a), Some(&b)) = (order.first(), order.get(1)) else { return 0 };
+            let t = segment_position(p, to_f32(palette[a]), to_f32(palette[b]));
+            let threshold = (matrix[y as usize % N][x as usize % N] as f32 + 0.5) / denom;
+            if t > threshold { b as u8 } else { a as u8 }
+        })
+        .collect()
+}
+
+// Position (0..1) of `p` projected onto the segment a-b in weighted RGB
+fn segment_position(p: [f32; 3], a: [f32; 3], b: [f32; 3]) -> f32 {
+    let mut ab_ab = 0.0;
+    let mut ap_ab = 0.0;
+    for ch in 0..3 {
+        let ab = b[ch] - a[ch];
+        ab_ab += WEIGHTS[ch] * ab * ab;
+        ap_ab += WEIGHTS[ch] * (p[ch] - a[ch]) * ab;
+    }
+    if ab_ab > 0.0 { (ap_ab / ab_ab).clamp(0.0, 1.0) } else { 0.0 }
+}
+
+/// Squared weighted RGB distance from `p` to the segment a-b, i.e. to the colors a two-color
+/// dither between a and b can approximate. Used to pick color pairs per cell.
+pub(crate) fn segment_distance(p: [f32; 3], a: [f32; 3], b: [f32; 3]) -> f32 {
+    let t = segment_position(p, a, b);
+    (0..3)
+        .map(|ch| {
+            let d = p[ch] - (a[ch] + t * (b[ch] - a[ch]));
+            WEIGHTS[ch] * d * d
+        })
+        .sum()
+}
+
+/// Squared weighted RGB distance between two colors.
+pub(crate) fn color_distance(a: [f32; 3], b: [f32; 3]) -> f32 {
+    (0..3).map(|ch| WEIGHTS[ch] * (a[ch] - b[ch]).powi(2)).sum()
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+
+use image::Rgba;
+
+use super::{dither_cells, flatten_alpha};
+use crate::engine::algorithms::RgbaImage;
+use crate::engine::color::rgb_to_lab;
+
+pub const WIDTH: u32 = 256;
+pub const HEIGHT: u32 = 240;
+/// Pattern tile size in pixels
+pub const TILE: u32 = 8;
+/// Attribute area size in pixels; each area uses one sub-palette
+pub const AREA: u32 = 16;
+/// Tiles in one pattern table
+pub const MAX_TILES: usize = 256;
+const SUB_PALETTES: usize = 4;
+// Assign/refit rounds for the sub-palette optimization
+const GROUP_ITERATIONS: usize = 8;
+
+/// 2C02 master palette in NES color number order.
+pub const NES_PALETTE: [[u8; 3]; 64] = [
+    [0x7C, 0x7C, 0x7C], [0x00, 0x00, 0xFC], [0x00, 0x00, 0xBC], [0x44, 0x28, 0xBC],
+    [0x94, 0x00, 0x84], [0xA8, 0x00, 0x20], [0xA8, 0x10, 0x00], [0x88, 0x14, 0x00],
+    [0x50, 0x30, 0x00], [0x00, 0x78, 0x00], [0x00, 0x68, 0x00], [0x00, 0x58, 0x00],
+    [0x00, 0x40, 0x58], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+    [0xBC, 0xBC, 0xBC], [0x00, 0x78, 0xF8], [0x00, 0x58, 0xF8], [0x68, 0x44, 0xFC],
+    [0xD8, 0x00, 0xCC], [0xE4, 0x00, 0x58], [0xF8, 0x38, 0x00], [0xE4, 0x5C, 0x10],
+    [0xAC, 0x7C, 0x00], [0x00, 0xB8, 0x00], [0x00, 0xA8, 0x00], [0x00, 0xA8, 0x44],
+    [0x00, 0x88, 0x88], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+    [0xF8, 0xF8, 0xF8], [0x3C, 0xBC, 0xFC], [0x68, 0x88, 0xFC], [0x98, 0x78, 0xF8],
+    [0xF8, 0x78, 0xF8], [0xF8, 0x58, 0x98], [0xF8, 0x78, 0x58], [0xFC, 0xA0, 0x44],
+    [0xF8, 0xB8, 0x00], [0xB8, 0xF8, 0x18], [0x58, 0xD8, 0x54], [0x58, 0xF8, 0x98],
+    [0x00, 0xE8, 0xD8], [0x78, 0x78, 0x78], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+    [0xFC, 0xFC, 0xFC], [0xA4, 0xE4, 0xFC], [0xB8, 0xB8, 0xF8], [0xD8, 0xB8, 0xF8],
+    [0xF8, 0xB8, 0xF8], [0xF8, 0xA4, 0xC0], [0xF0, 0xD0, 0xB0], [0xFC, 0xE0, 0xA8],
+    [0xF8, 0xD8, 0x78], [0xD8, 0xF8, 0x78], [0xB8, 0xF8, 0xB8], [0xB8, 0xF8, 0xD8],
+    [0x00, 0xFC, 0xFC], [0xF8, 0xD8, 0xF8], [0x00, 0x00, 0x00], [0x00, 0x00, 0x00],
+];
+
+/// The 54 usable color numbers: 0x0D ("blacker than black") and the mirrored blacks are
+/// skipped in favour of 0x0F, and 0x20 in favour of 0x30 (the same white on hardware).
+pub fn usable_colors() -> Vec<u8> {
+    (0x00..=0x0C)
+        .chain([0x0F])
+        .chain(0x10..=0x1C)
+        .chain(0x21..=0x2D)
+        .chain(0x30..=0x3D)
+        .collect()
+}
+
+/// A rendered background in hardware terms.
+pub struct NesScreen {
+    /// Universal background color (color number), entry 0 of every sub-palette
+    pub backdrop: u8,
+    /// Colors 1..3 of each sub-palette (color numbers)
+    pub sub_palettes: [[u8; 3]; SUB_PALETTES],
+    /// Unique tiles as 64 pixel values (0..3), row-major; at most 256
+    pub tiles: Vec<[u8; 64]>,
+    /// 32x30 tile numbers, row-major
+    pub nametable: Vec<u8>,
+    /// Sub-palette number per 16x16 area, 16x15 row-major
+    pub areas: Vec<u8>,
+    /// Unique tiles the dithered screen had before any were substituted to fit
+    pub unique_tiles: usize,
+}
+
+impl NesScreen {
+    /// RGB of pixel value `value` (0..3) in sub-palette `sub`.
+    pub fn color(&self, sub: u8, value: u8) -> [u8; 3] {
+        let number = if value == 0 { self.backdrop } else { self.sub_palettes[sub as usize][value as usize - 1] };
+        NES_PALETTE[number as usize]
+    }
+}
+
+/// Warnings for a screen whose unique tiles overflowed the pattern table.
+pub fn tile_warnings(screen: &NesScreen) -> Vec<String> {
+    let n = screen.unique_tiles;
+    if n <= MAX_TILES {
+        return Vec::new();
+    }
+    vec![format!(
+        "{n} unique tiles exceed the {MAX_TILES} one pattern table holds; the {} least used were replaced by their closest kept tile",
+        n - screen.tiles.len()
+    )]
+}
+
+// Total error of an area histogram (counts per usable color) under a backdrop plus three
+// colors, each bin paying its distance to the closest of the four
+fn histogram_error(hist: &[u32], dist: &[Vec<f32>], backdrop: usize, colors: &[usize; 3]) -> f32 {
+    hist.iter()
+        .enumerate()
+        .filter(|(_, &n)| n > 0)
+        .map(|(k, &n)| {
+            let d = colors.iter().fold(dist[k][backdrop], |m, &c| m.min(dist[k][c]));
+            n as f32 * d
+        })
+        .sum()
+}
+
+// Three colors for a histogram: greedy picks, then one pass of per-slot replacement
+fn fit_colors(hist: &[u32], dist: &[Vec<f32>], backdrop: usize) -> [usize; 3] {
+    let n = dist.len();
+    let mut colors = [backdrop; 3];
+    for slot in 0..3 {
+        colors[slot] = (0..n)
+            .min_by(|&a, &b| {
+                let mut ca = colors;
+                ca[slot] = a;
+                let mut cb = colors;
+                cb[slot] = b;
+                histogram_error(hist, dist, backdrop, &ca).total_cmp(&histogram_error(hist, dist, backdrop, &cb))
+            })
+            .unwrap_or(backdrop);
+    }
+    for slot in 0..3 {
+        let mut best = histogram_error(hist, dist, backdrop, &colors);
+        for c in 0..n {
+            let mut trial = colors;
+            trial[slot] = c;
+            let e = histogram_error(hist, dist, backdrop, &trial);
+            if e < best {
+                best = e;
+                colors = trial;
+            }
+        }
+    }
+    colors
+}
+
+/// Picks a shared backdrop and four 3-color sub-palettes from the 2C02 palette and assigns
+/// one per 16x16 area (alternating refits of the palettes and reassignment of the areas),
+/// dithers the screen in one pass with the named algorithm, each area inside its
+/// sub-palette, then builds the pattern table. Past 256 unique tiles the least used ones are
+/// replaced by their closest kept tile. `img` (256x240) is rewritten to exactly what the
+/// hardware would show.
+pub fn render_nes(img: &mut RgbaImage, algorithm: &str) -> NesScreen {
+    let usable = usable_colors();
+    let labs: Vec<[f32; 3]> = usable
+        .iter()
+        .map(|&c| {
+            let [r, g, b] = NES_PALETTE[c as usize];
+            rgb_to_lab(r, g, b)
+        })
+        .collect();
+    let dist: Vec<Vec<f32>> = labs
+        .iter()
+        .map(|a| labs.iter().map(|b| (0..3).map(|k| (a[k] - b[k]).powi(2)).sum()).collect())
+        .collect();
+    let nearest = |p: &Rgba<u8>| {
+        let lab = rgb_to_lab(p.0[0], p.0[1], p.0[2]);
+        (0..labs.len())
+            .min_by(|&a, &b| {
+                let da: f32 = (0..3).map(|k| (lab[k] - labs[a][k]).powi(2)).sum();
+                let db: f32 = (0..3).map(|k| (lab[k] - labs[b][k]).powi(2)).sum();
+                da.total_cmp(&db)
+            })
+            .unwrap_or(0)
+    };
+
+    // Per-area histograms of the nearest usable color, opaque pixels only
+    let (w, h) = (img.width().min(WIDTH), img.height().min(HEIGHT));
+    let (areas_x, areas_y) = (WIDTH / AREA, HEIGHT.div_ceil(AREA));
+    let area_count = (areas_x * areas_y) as usize;
+    let mut hists = vec![vec![0u32; usable.len()]; area_count];
+    for (x, y, p) in img.enumerate_pixels() {
+        if x >= w || y >= h || p.0[3] < 128 { continue; }
+        hists[((y / AREA) * areas_x + x / AREA) as usize][nearest(p)] += 1;
+    }
+    let mut total = vec![0u32; usable.len()];
+    for hist in &hists {
+        for (t, &n) in total.iter_mut().zip(hist) { *t += n; }
+    }
+    let backdrop = (0..usable.len()).max_by_key(|&k| total[k]).unwrap_or(0);
+
+    // Seed: one palette for the whole image, then one each for the worst-served areas
+    let mut palettes = vec![fit_colors(&total, &dist, backdrop)];
+    while palettes.len() < SUB_PALETTES {
+        let area_error = |hist: &Vec<u32>| {
+            palettes.iter().map(|p| histogram_error(hist, &dist, backdrop, p)).fold(f32::INFINITY, f32::min)
+        };
+        let worst = hists.iter().max_by(|a, b| area_error(a).total_cmp(&area_error(b)));
+        let seed = worst.map_or(palettes[0], |hist| fit_colors(hist, &dist, backdrop));
+        palettes.push(seed);
+    }
+    let mut assignment = vec![0usize; area_count];
+    for _ in 0..GROUP_ITERATIONS {
+        for (a, hist) in hists.iter().enumerate() {
+            assignment[a] = (0..SUB_PALETTES)
+                .min_by(|&i, &j| {
+                    histogram_error(hist, &dist, backdrop, &palettes[i])
+                        .total_cmp(&histogram_error(hist, &dist, backdrop, &palettes[j]))
+                })
+                .unwrap_or(0);
+        }
+        for (g, palette) in palettes.iter_mut().enumerate() {
+            let mut group = vec![0u32; usable.len()];
+            for (hist, _) in hists.iter().zip(&assignment).filter(|(_, &a)| a == g) {
+                for (t, &n) in group.iter_mut().zip(hist) { *t += n; }
+            }
+            if group.iter().any(|&n| n > 0) {
+                *palette = fit_colors(&group, &dist, backdrop);
+            }
+        }
+    }
+
+    let mut screen = NesScreen {
+        backdrop: usable[backdrop],
+        sub_palettes: [[0; 3]; SUB_PALETTES],
+        tiles: Vec::new(),
+        nametable: vec![0; ((WIDTH / TILE) * (HEIGHT / TILE)) as usize],
+        areas: assignment.iter().map(|&a| a as u8).collect(),
+        unique_tiles: 0,
+    };
+    for (sub, palette) in screen.sub_palettes.iter_mut().zip(&palettes) {
+        *sub = palette.map(|c| usable[c]);
+    }
+
+    // Dither the whole screen in one pass, each area inside its sub-palette
+    flatten_alpha(img, NES_PALETTE[screen.backdrop as usize]);
+    let (img_w, img_h) = img.dimensions();
+    let mut colors = Vec::new();
+    for ay in 0..img_h.div_ceil(AREA) {
+        for ax in 0..img_w.div_ceil(AREA) {
+            let sub = if ax < areas_x && ay < areas_y { screen.areas[(ay * areas_x + ax) as usize] } else { 0 };
+            colors.push((0..4).map(|v| screen.color(sub, v)).collect());
+        }
+    }
+    let dithered = dither_cells(img, algorithm, (AREA, AREA), &colors);
+    let mut values = vec![0u8; (WIDTH * HEIGHT) as usize];
+    for y in 0..h {
+        for x in 0..w {
+            values[(y * WIDTH + x) as usize] = dithered[(y * img_w + x) as usize];
+        }
+    }
+
+    // Pattern table: unique tiles in order of first use
+    let tiles_x = WIDTH / TILE;
+    let tile_at = |tx: u32, ty: u32| {
+        let mut tile = [0u8; 64];
+        for (k, v) in tile.iter_mut().enumerate() {
+            let (x, y) = (tx * TILE + k as u32 % TILE, ty * TILE + k as u32 / TILE);
+            *v = values[(y * WIDTH + x) as usize];
+        }
+        tile
+    };
+    let mut index: HashMap<[u8; 64], usize> = HashMap::new();
+    let mut uses: Vec<usize> = Vec::new();
+    let mut refs = Vec::with_capacity(screen.nametable.len());
+    for ty in 0..HEIGHT / TILE {
+        for tx in 0..tiles_x {
+            let tile = tile_at(tx, ty);
+            let next = screen.tiles.len();
+            let i = *index.entry(tile).or_insert(next);
+            if i == next {
+                screen.tiles.push(tile);
+                uses.push(0);
+            }
+            uses[i] += 1;
+            refs.push(i);
+        }
+    }
+    screen.unique_tiles = screen.tiles.len();
+    let mut remap: Vec<usize> = (0..screen.tiles.len()).collect();
+    if screen.tiles.len() > MAX_TILES {
+        let mut order: Vec<usize> = (0..screen.tiles.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(uses[i]));
+        let mut kept = order[..MAX_TILES].to_vec();
+        kept.sort_unstable();
+        let differing = |a: &[u8; 64], b: &[u8; 64]| a.iter().zip(b).filter(|(x, y)| x != y).count();
+        for (i, slot) in remap.iter_mut().enumerate() {
+            *slot = match kept.binary_search(&i) {
+                Ok(pos) => pos,
+                Err(_) => (0..kept.len())
+                    .min_by_key(|&k| differing(&screen.tiles[i], &screen.tiles[kept[k]]))
+                    .unwrap_or(0),
+            };
+        }
+        screen.tiles = kept.iter().map(|&i| screen.tiles[i]).collect();
+    }
+    for (slot, &r) in screen.nametable.iter_mut().zip(&refs) {
+        *slot = remap[r] as u8;
+    }
+
+    // Redraw from the final tiles so the image matches the exported data
+    for ty in 0..HEIGHT / TILE {
+        for tx in 0..tiles_x {
+            let tile = &screen.tiles[screen.nametable[(ty * tiles_x + tx) as usize] as usize];
+            for (k, &v) in tile.iter().enumerate() {
+                let (x, y) = (tx * TILE + k as u32 % TILE, ty * TILE + k as u32 / TILE);
+                if x >= w || y >= h { continue; }
+                let sub = screen.areas[((y / AREA) * areas_x + x / AREA) as usize];
+                let [r, g, b] = screen.color(sub, v);
+                img.put_pixel(x, y, Rgba([r, g, b, 255]));
+            }
+        }
+    }
+    screen
+}
@@ -0,0 +1,35 @@
+use super::{dither_block, flatten_alpha};
+use crate::engine::algorithms::RgbaImage;
+
+/// Print head widths in dots: 58 mm, 80 mm and 112 mm paper at 203 dpi.
+pub const DOT_WIDTHS: [u32; 3] = [384, 576, 832];
+/// Paper first, so index 0 is "no dot"
+pub const PAPER_INK: [[u8; 3]; 2] = [[255, 255, 255], [0, 0, 0]];
+
+/// Parses "escpos" / "thermal" (384 dots) or a width suffix, e.g. "escpos_576"; only the
+/// widths in [`DOT_WIDTHS`] are accepted.
+pub fn dots_from_name(name: &str) -> Option<u32> {
+    let rest = name.strip_prefix("escpos").or_else(|| name.strip_prefix("thermal"))?;
+    let rest = rest.trim_start_matches('_');
+    if rest.is_empty() {
+        return Some(DOT_WIDTHS[0]);
+    }
+    let dots: u32 = rest.parse().ok()?;
+    DOT_WIDTHS.contains(&dots).then_some(dots)
+}
+
+/// Height in dots for a source of `width` x `height` printed `dots` wide.
+pub fn print_height(dots: u32, (width, height): (u32, u32)) -> u32 {
+    ((height as f64 * dots as f64 / width.max(1) as f64).round() as u32).max(1)
+}
+
+/// Dithers `img` to 1-bit with the named algorithm, transparency printing as paper, and
+/// returns whether each dot is burned, row-major.
+pub fn render_thermal(img: &mut RgbaImage, algorithm: &str) -> Vec<bool> {
+    let (w, h) = img.dimensions();
+    flatten_alpha(img, PAPER_INK[0]);
+    dither_block(img, (0, 0, w, h), algorithm, &PAPER_INK)
+        .into_iter()
+        .map(|i| i == 1)
+        .collect()
+}
@@ -0,0 +1,94 @@
+use super::{dither_cells, flatten_alpha, segment_distance};
+use crate::engine::algorithms::RgbaImage;
+
+pub const WIDTH: u32 = 256;
+pub const HEIGHT: u32 = 192;
+/// Attribute cell size in pixels
+pub const CELL: u32 = 8;
+const NORMAL_LEVEL: u8 = 0xD7;
+const BRIGHT_LEVEL: u8 = 0xFF;
+
+/// Spectrum color number 0..7 (bit 0 blue, bit 1 red, bit 2 green) at normal or BRIGHT level.
+pub fn spectrum_color(index: u8, bright: bool) -> [u8; 3] {
+    let level = if bright { BRIGHT_LEVEL } else { NORMAL_LEVEL };
+    let on = |bit: u8| if index & bit != 0 { level } else { 0 };
+    [on(2), on(4), on(1)]
+}
+
+/// The 15 distinct colors: normal 0..7, then BRIGHT 1..7 (BRIGHT black is black).
+pub fn spectrum_palette() -> Vec<[u8; 3]> {
+    (0..8).map(|i| spectrum_color(i, false)).chain((1..8).map(|i| spectrum_color(i, true))).collect()
+}
+
+/// A rendered screen in hardware terms.
+pub struct ZxScreen {
+    /// One flag per pixel, row-major over 256x192; true where the pixel shows INK
+    pub ink: Vec<bool>,
+    /// 32x24 attribute bytes, row-major: INK in bits 0-2, PAPER in bits 3-5, BRIGHT in bit 6
+    pub attributes: Vec<u8>,
+}
+
+// Best (ink, paper, bright) for a cell's colors, over both brightness groups
+fn best_pair(pixels: &[[f32; 3]]) -> (u8, u8, bool) {
+    let mut best = (0, 0, false);
+    let mut best_cost = f32::INFINITY;
+    for bright in [false, true] {
+        let colors: Vec<[f32; 3]> = (0..8)
+            .map(|i| spectrum_color(i, bright).map(|c| c as f32))
+            .collect();
+        for a in 0..8u8 {
+            for b in a..8u8 {
+                let (ca, cb) = (colors[a as usize], colors[b as usize]);
+                let cost: f32 = pixels.iter().map(|&p| segment_distance(p, ca, cb)).sum();
+                if cost < best_cost {
+                    best_cost = cost;
+                    best = (a, b, bright);
+                }
+            }
+        }
+    }
+    best
+}
+
+/// Renders `img` (256x192) under Spectrum attribute clash: each 8x8 cell picks the INK/PAPER
+/// pair from one brightness group that best spans its colors, then the whole screen is
+/// dithered in one pass with the named algorithm, each pixel restricted to its cell's pair.
+/// Transparency is flattened onto black. The cell's majority color becomes PAPER.
+pub fn render_zx_spectrum(img: &mut RgbaImage, algorithm: &str) -> ZxScreen {
+    let (w, h) = img.dimensions();
+    flatten_alpha(img, [0, 0, 0]);
+    let mut pairs = Vec::new();
+    let mut palettes = Vec::new();
+    for cy in (0..h).step_by(CELL as usize) {
+        for cx in (0..w).step_by(CELL as usize) {
+            let (cw, ch) = (CELL.min(w - cx), CELL.min(h - cy));
+            let pixels: Vec<[f32; 3]> = image::imageops::crop_imm(img, cx, cy, cw, ch)
+                .to_image()
+                .pixels()
+                .map(|p| [p.0[0] as f32, p.0[1] as f32, p.0[2] as f32])
+                .collect();
+            let (a, b, bright) = best_pair(&pixels);
+            palettes.push(vec![spectrum_color(a, bright), spectrum_color(b, bright)]);
+            pairs.push((cx, cy, a, b, bright));
+        }
+    }
+    let indices = dither_cells(img, algorithm, (CELL, CELL), &palettes);
+    let mut screen = ZxScreen {
+        ink: vec![false; (WIDTH * HEIGHT) as usize],
+        attributes: vec![0; ((WIDTH / CELL) * (HEIGHT / CELL)) as usize],
+    };
+    for (cx, cy, a, b, bright) in pairs {
+        if cx >= WIDTH || cy >= HEIGHT { continue; }
+        let (cw, ch) = (CELL.min(w - cx), CELL.min(h - cy));
+        let cell: Vec<(u32, u32)> = (cy..cy + ch).flat_map(|y| (cx..cx + cw).map(move |x| (x, y))).collect();
+        let on_b = |&(x, y): &(u32, u32)| indices[(y * w + x) as usize] == 1 && a != b;
+        let b_count = cell.iter().filter(|p| on_b(p)).count();
+        let (ink, paper, ink_is_b) = if b_count * 2 <= cell.len() { (b, a, true) } else { (a, b, false) };
+        for p in &cell {
+            screen.ink[(p.1 * WIDTH + p.0) as usize] = on_b(p) == ink_is_b && a != b;
+        }
+        screen.attributes[((cy / CELL) * (WIDTH / CELL) + cx / CELL) as usize] =
+            ink | (paper << 3) | ((bright as u8) << 6);
+    }
+    screen
+}
@@ -0,0 +1,57 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::path::BaseDirectory;
+use tauri::Manager;
+
+#[derive(Debug, Clone)]
+pub struct LutEntry {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+fn luts_dir(app: &tauri::AppHandle) -> Option<PathBuf> {
+    // Resolve Resource/luts, then target/.../resources/luts, then compile-time src-tauri/resources/luts
+    app.path()
+        .resolve("luts", BaseDirectory::Resource)
+        .ok()
+        .filter(|p| p.exists())
+        .or_else(|| {
+            app.path()
+                .resource_dir()
+                .ok()
+                .map(|p| p.join("luts"))
+                .filter(|p| p.exists())
+        })
+        .or_else(|| {
+            let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            let dev = root.join("resources").join("luts");
+            if dev.exists() { Some(dev) } else { None }
+        })
+}
+
+/// Lists `.cube` files in the bundled luts folder, named by file stem.
+pub fn load_luts(app: &tauri::AppHandle) -> Vec<LutEntry> {
+    let mut out: Vec<LutEntry> = Vec::new();
+    if let Some(dir) = luts_dir(app) {
+        if let Ok(rd) = fs::read_dir(&dir) {
+            for ent in rd.flatten() {
+                let path = ent.path();
+                if path.extension().and_then(|e| e.to_str()).map(|e| e.eq_ignore_ascii_case("cube")).unwrap_or(false) {
+                    let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("LUT").to_string();
+                    out.push(LutEntry { name, path });
+                }
+            }
+        }
+    }
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+/// Finds a LUT by listed name, or accepts an absolute path to a `.cube` file.
+pub fn resolve_lut_path(app: &tauri::AppHandle, name: &str) -> Option<PathBuf> {
+    if let Some(e) = load_luts(app).into_iter().find(|e| e.name == name) {
+        return Some(e.path);
+    }
+    let p = Path::new(name);
+    if p.is_absolute() && p.is_file() { Some(p.to_path_buf()) } else { None }
+}
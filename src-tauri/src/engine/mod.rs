@@ -1,7 +1,18 @@
 pub mod algorithms;
 pub mod pipeline;
 pub mod color;
+pub mod decode;
 pub mod palettes;
+pub mod luts;
 pub mod dither;
 pub mod filters;
+pub mod native_grid;
+pub mod transform;
 
+pub mod alpha;
+pub mod export;
+pub mod prefilters;
+pub mod postprocess;
+pub mod scalers;
+pub mod hardware;
+pub mod text;
@@ -0,0 +1,172 @@
+use image::{Rgba, Rgba32FImage};
+
+// Largest upscale factor we try to detect; screenshots are rarely blown up further
+const MAX_SCALE: usize = 32;
+// Mean edge energy on the detected lattice must exceed the overall mean by this factor
+const MIN_LATTICE_SCORE: f32 = 1.6;
+// A divisor of the best period is preferred when it scores at least this fraction of the best
+const DIVISOR_TOLERANCE: f32 = 0.8;
+
+/// Pixel lattice of an upscaled pixel-art image along one axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisLattice {
+    /// Source pixels per logical pixel
+    pub scale: u32,
+    /// Offset of the first full cell boundary in source pixels (0..scale)
+    pub phase: u32,
+}
+
+/// Detected native pixel grid of an upscaled pixel-art image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NativeGrid {
+    pub x: AxisLattice,
+    pub y: AxisLattice,
+    /// Logical grid size after resampling
+    pub width: u32,
+    pub height: u32,
+}
+
+#[inline]
+fn luma(p: &Rgba<f32>) -> f32 {
+    (0.299 * p[0] + 0.587 * p[1] + 0.114 * p[2]) * 255.0
+}
+
+// Edge energy per boundary: profile[i] is the summed |L(i) - L(i-1)| across the other axis
+fn edge_profiles(img: &Rgba32FImage) -> (Vec<f32>, Vec<f32>) {
+    let (w, h) = (img.width() as usize, img.height() as usize);
+    let lum: Vec<f32> = img.pixels().map(luma).collect();
+    let mut cols = vec![0.0f32; w];
+    let mut rows = vec![0.0f32; h];
+    for y in 0..h {
+        let row = &lum[y * w..(y + 1) * w];
+        for x in 1..w {
+            cols[x] += (row[x] - row[x - 1]).abs();
+        }
+        if y > 0 {
+            let prev = &lum[(y - 1) * w..y * w];
+            rows[y] = row.iter().zip(prev).map(|(a, b)| (a - b).abs()).sum();
+        }
+    }
+    (cols, rows)
+}
+
+// Best phase for a period and its score (lattice mean / overall mean)
+fn score_period(profile: &[f32], period: usize, mean_all: f32) -> (usize, f32) {
+    let mut best = (0usize, 0.0f32);
+    for phase in 0..period {
+        let mut sum = 0.0f32;
+        let mut n = 0usize;
+        let mut i = if phase == 0 { period } else { phase };
+        while i < profile.len() {
+            sum += profile[i];
+            n += 1;
+            i += period;
+        }
+        if n == 0 { continue; }
+        let score = (sum / n as f32) / mean_all;
+        if score > best.1 { best = (phase, score); }
+    }
+    best
+}
+
+fn detect_axis(profile: &[f32]) -> Option<AxisLattice> {
+    let len = profile.len();
+    if len < 8 { return None; }
+    let mean_all = profile[1..].iter().sum::<f32>() / (len - 1) as f32;
+    if mean_all <= f32::EPSILON { return None; }
+    // Need at least four cells along the axis to trust the periodicity
+    let max_period = MAX_SCALE.min(len / 4);
+    if max_period < 2 { return None; }
+    let scores: Vec<(usize, usize, f32)> = (2..=max_period)
+        .map(|p| {
+            let (phase, s) = score_period(profile, p, mean_all);
+            (p, phase, s)
+        })
+        .collect();
+    let &(best_p, _, best_s) = scores
+        .iter()
+        .max_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(std::cmp::Ordering::Equal))?;
+    if best_s < MIN_LATTICE_SCORE { return None; }
+    // Multiples of the true period sample a subset of the same boundaries and score
+    // just as high, so fall back to the smallest divisor that still scores well
+    let (p, phase, _) = scores
+        .iter()
+        .copied()
+        .filter(|(p, _, s)| best_p % p == 0 && *s >= best_s * DIVISOR_TOLERANCE)
+        .min_by_key(|(p, _, _)| *p)?;
+    Some(AxisLattice { scale: p as u32, phase: phase as u32 })
+}
+
+// Cell spans along one axis; partial border cells are kept when at least half a cell wide
+fn cell_spans(len: u32, lattice: AxisLattice) -> Vec<(u32, u32)> {
+    let AxisLattice { scale, phase } = lattice;
+    let mut spans = Vec::new();
+    if phase > 0 && phase * 2 >= scale {
+        spans.push((0, phase));
+    }
+    let mut start = phase;
+    while start < len {
+        let end = (start + scale).min(len);
+        if (end - start) * 2 >= scale {
+            spans.push((start, end));
+        }
+        start = end;
+    }
+    spans
+}
+
+/// Estimates the original pixel grid of an upscaled pixel-art image from edge periodicity.
+/// Returns `None` when no clear lattice is found (e.g. photos or native-resolution art).
+pub fn detect_native_grid(img: &Rgba32FImage) -> Option<NativeGrid> {
+    let (cols, rows) = edge_profiles(img);
+    let x = detect_axis(&cols);
+    let y = detect_axis(&rows);
+    // Square pixels are the common case, so borrow the scale from the other axis when
+    // one axis is featureless (e.g. horizontal stripes)
+    let (x, y) = match (x, y) {
+        (Some(x), Some(y)) => (x, y),
+        (Some(x), None) => (x, AxisLattice { scale: x.scale, phase: 0 }),
+        (None, Some(y)) => (AxisLattice { scale: y.scale, phase: 0 }, y),
+        (None, None) => return None,
+    };
+    let width = cell_spans(img.width(), x).len() as u32;
+    let height = cell_spans(img.height(), y).len() as u32;
+    if width == 0 || height == 0 { return None; }
+    Some(NativeGrid { x, y, width, height })
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    values.sort_unstable_by(f32::total_cmp);
+    values[values.len() / 2]
+}
+
+/// Resamples each detected cell to a single color using the per-channel median of its
+/// interior, which rejects JPEG ringing and blending along cell borders.
+pub fn resample_native(img: &Rgba32FImage, grid: &NativeGrid) -> Rgba32FImage {
+    let xs = cell_spans(img.width(), grid.x);
+    let ys = cell_spans(img.height(), grid.y);
+    let mut out = Rgba32FImage::new(xs.len() as u32, ys.len() as u32);
+    let inset = |(a, b): (u32, u32)| if b - a >= 3 { (a + 1, b - 1) } else { (a, b) };
+    let mut channels: [Vec<f32>; 4] = Default::default();
+    for (cy, &ys_span) in ys.iter().enumerate() {
+        let (y0, y1) = inset(ys_span);
+        for (cx, &xs_span) in xs.iter().enumerate() {
+            let (x0, x1) = inset(xs_span);
+            for c in channels.iter_mut() { c.clear(); }
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let p = img.get_pixel(x, y).0;
+                    for (c, v) in channels.iter_mut().zip(p) { c.push(v); }
+                }
+            }
+            let px = [
+                median(&mut channels[0]),
+                median(&mut channels[1]),
+                median(&mut channels[2]),
+                median(&mut channels[3]),
+            ];
+            out.put_pixel(cx as u32, cy as u32, Rgba(px));
+        }
+    }
+    out
+}
@@ -1,26 +1,30 @@
-use crate::types::RenderRequest;
-use image::{imageops::FilterType, DynamicImage, ImageFormat, Rgba, RgbaImage};
+use crate::types::{ExportedFile, HardwareExport, RenderRequest};
+use image::{imageops::FilterType, DynamicImage, ImageFormat, Rgba, Rgba32FImage, RgbaImage};
 use std::f32::consts::PI;
 use std::io::Cursor;
 use thiserror::Error;
 
-use super::algorithms::get_algorithm_by_name;
-use super::dither::{
-    atkinson::apply_atkinson,
-    bayer::{Bayer, Bayer2, Bayer8},
-    burkes::apply_burkes,
-    dual_color::apply_dual_color,
-    edge::apply_edge_dithering,
-    floyd_steinberg::FloydSteinberg,
-    jarvis_judice_ninke::apply_jjn,
-    ordered_selective::apply_ordered_selective,
-    randomized_selective::apply_randomized_selective,
-    selective::apply_selective,
-    sierra::{apply_sierra, apply_sierra_lite, apply_two_row_sierra},
-    stucki::apply_stucki,
-};
+use super::color::{hex_to_rgb, rgb_to_lab};
+use super::decode::{load_image_from_memory, to_working_image, HdrToneMap};
+use super::alpha::{apply_alpha_mode, AlphaMode};
+use super::dither::apply_dither_by_name;
+use super::export::indexed_png::encode_indexed_png;
+use super::export::text::encode_text;
+use super::hardware::{render_hardware, HardwareFile, HardwareMode};
+use super::scalers::{upscale_pixel_art, PixelScaler};
+use super::transform::apply_transform;
+use super::native_grid::{detect_native_grid, resample_native, NativeGrid};
 use super::palettes::get_palette_by_name;
-use crate::engine::algorithms::Algorithm;
+use super::postprocess::cleanup::apply_cleanup;
+use super::postprocess::outline::{apply_outline, Outline};
+use super::prefilters::background::{border_key_color, remove_background, BackgroundRemoval};
+use super::prefilters::color_transfer::{apply_color_transfer, ColorTransfer};
+use super::prefilters::edge_preserving::{apply_edge_preserving, EdgePreservingFilter};
+use super::prefilters::equalize::{apply_equalize, Equalize};
+use super::prefilters::lut::{CubeLut, LutError, LutInterpolation};
+use super::prefilters::sharpen::{laplacian_boost, unsharp_mask, SharpenStage};
+use super::prefilters::tone::{auto_levels, build_tone_lut};
+use super::text::{match_glyphs, TextFormat, CELL, DEFAULT_CHARSET};
 
 #[derive(Debug, Error)]
 pub enum EngineError {
@@ -28,6 +32,14 @@ pub enum EngineError {
     UnsupportedDataUrl,
     #[error(transparent)]
     Image(#[from] image::ImageError),
+    #[error(transparent)]
+    PngEncode(#[from] png::EncodingError),
+    #[error(transparent)]
+    Lut(#[from] LutError),
+    #[error("no hardware mode selected")]
+    NoHardwareMode,
+    #[error("no pixel grid detected; choose a grid size instead of auto")]
+    NoNativeGrid,
 }
 
 fn decode_data_url_to_image(data_url: &str) -> Result<DynamicImage, EngineError> {
@@ -43,21 +55,58 @@ fn decode_data_url_to_image(data_url: &str) -> Result<DynamicImage, EngineError>
     let bytes = B64
         .decode(b64)
         .map_err(|_| EngineError::UnsupportedDataUrl)?;
-    let img = image::load_from_memory(&bytes)?;
+    let img = load_image_from_memory(&bytes)?;
     Ok(img)
 }
 
-fn resize_to_grid(img: &DynamicImage, grid_w: u32, grid_h: u32) -> RgbaImage {
-    img.resize_exact(grid_w, grid_h, FilterType::Nearest)
-        .to_rgba8()
+// Float working image for the source-domain stages; HDR sources are tone mapped here
+fn decode_working_image(req: &RenderRequest) -> Result<Rgba32FImage, EngineError> {
+    let img = decode_data_url_to_image(&req.image_data_url)?;
+    Ok(to_working_image_for(img, req))
+}
+
+fn to_working_image_for(img: DynamicImage, req: &RenderRequest) -> Rgba32FImage {
+    to_working_image(
+        img,
+        HdrToneMap::from_name(req.hdr_tone_map.as_deref()),
+        req.hdr_exposure.unwrap_or(0.0),
+    )
+}
+
+// Crop / perspective / rotate / flip, ahead of every prefilter
+fn apply_transform_stage(img: Rgba32FImage, req: &RenderRequest) -> Rgba32FImage {
+    match req.transform.as_ref() {
+        Some(t) => apply_transform(img, t),
+        None => img,
+    }
+}
+
+// Grid-domain stages and the dithers work on 8-bit; this is the single quantization point
+fn quantize_to_rgba8(img: Rgba32FImage) -> RgbaImage {
+    DynamicImage::ImageRgba32F(img).to_rgba8()
+}
+
+// Secondary inputs (e.g. reference images) may be a data URL or a local file path
+fn decode_image_source(src: &str) -> Result<DynamicImage, EngineError> {
+    if src.trim_start().starts_with("data:") {
+        decode_data_url_to_image(src)
+    } else {
+        // Same decode path as data URLs, so EXIF orientation and ICC conversion apply
+        let bytes = std::fs::read(src.trim()).map_err(image::ImageError::from)?;
+        Ok(load_image_from_memory(&bytes)?)
+    }
+}
+
+fn resize_to_grid(img: &Rgba32FImage, grid_w: u32, grid_h: u32) -> Rgba32FImage {
+    image::imageops::resize(img, grid_w, grid_h, FilterType::Nearest)
 }
 fn apply_pre_color_adjustments(
-    img: &DynamicImage,
+    img: &Rgba32FImage,
     pre_contrast: Option<f32>,
     pre_saturation: Option<f32>,
     pre_hue_degrees: Option<f32>,
-) -> DynamicImage {
-    let mut rgba = img.to_rgba8();
+) -> Rgba32FImage {
+    let mut rgba = img.clone();
     let contrast = pre_contrast.unwrap_or(1.0);
     let saturation = pre_saturation.unwrap_or(1.0);
     let hue_deg = pre_hue_degrees.unwrap_or(0.0);
@@ -66,7 +115,7 @@ fn apply_pre_color_adjustments(
     let needs_saturation = (saturation - 1.0).abs() > 0.001;
     let needs_hue = hue_deg.abs() > 0.001;
     if !needs_contrast && !needs_saturation && !needs_hue {
-        return DynamicImage::ImageRgba8(rgba);
+        return rgba;
     }
 
     let c = (contrast).max(0.01);
@@ -99,10 +148,11 @@ fn apply_pre_color_adjustments(
     let m22 = lum_b + cos_a * one_minus_lum_b + sin_a * lum_b;
 
     for p in rgba.pixels_mut() {
+        // Math stays on the 0..255 scale of the original 8-bit implementation
         let [r, g, b, a] = p.0;
-        let mut rf = r as f32;
-        let mut gf = g as f32;
-        let mut bf = b as f32;
+        let mut rf = r * 255.0;
+        let mut gf = g * 255.0;
+        let mut bf = b * 255.0;
 
         if needs_contrast {
             rf = (rf - 128.0) * c + 128.0;
@@ -123,53 +173,103 @@ fn apply_pre_color_adjustments(
         }
 
         *p = Rgba([
-            rf.clamp(0.0, 255.0) as u8,
-            gf.clamp(0.0, 255.0) as u8,
-            bf.clamp(0.0, 255.0) as u8,
+            (rf / 255.0).clamp(0.0, 1.0),
+            (gf / 255.0).clamp(0.0, 1.0),
+            (bf / 255.0).clamp(0.0, 1.0),
             a,
         ]);
     }
-    DynamicImage::ImageRgba8(rgba)
+    rgba
+}
+
+fn apply_lut_prefilter(mut img: Rgba32FImage, req: &RenderRequest) -> Result<Rgba32FImage, EngineError> {
+    let Some(path) = req.lut_path.as_ref() else { return Ok(img); };
+    let lut = CubeLut::load_cached(path)?;
+    lut.apply(
+        &mut img,
+        req.lut_strength.unwrap_or(1.0),
+        LutInterpolation::from_name(req.lut_interpolation.as_deref()),
+    );
+    Ok(img)
+}
+
+fn apply_color_transfer_prefilter(mut img: Rgba32FImage, req: &RenderRequest) -> Result<Rgba32FImage, EngineError> {
+    let Some(src) = req.reference_image.as_deref().filter(|s| !s.trim().is_empty()) else {
+        return Ok(img);
+    };
+    let mut reference = decode_image_source(src)?;
+    // Statistics only need a representative sample of the reference
+    if reference.width() as u64 * reference.height() as u64 > 512 * 512 {
+        reference = reference.thumbnail(512, 512);
+    }
+    apply_color_transfer(
+        &mut img,
+        &to_working_image_for(reference, req),
+        ColorTransfer::from_name(req.color_transfer.as_deref()),
+        req.color_transfer_strength.unwrap_or(1.0),
+    );
+    Ok(img)
+}
+
+// Luminance-only histogram equalization / CLAHE, applied next to the pre-contrast stage
+fn apply_equalize_prefilter(mut img: Rgba32FImage, req: &RenderRequest) -> Rgba32FImage {
+    let Some(mode) = Equalize::from_request(req.equalize.as_deref(), req.clahe_tiles, req.clahe_clip_limit) else {
+        return img;
+    };
+    apply_equalize(&mut img, mode);
+    img
 }
 
-fn apply_night_vision_prefilter(img: &DynamicImage, enabled: bool) -> DynamicImage {
+// Background to alpha, ahead of the color prefilters so the key matches the source colors
+fn apply_background_prefilter(mut img: Rgba32FImage, req: &RenderRequest) -> Rgba32FImage {
+    let Some(mode) = req.background_removal.as_deref().and_then(BackgroundRemoval::from_name) else {
+        return img;
+    };
+    let key = match req.background_color.as_deref().and_then(hex_to_rgb) {
+        Some([r, g, b]) => Some(rgb_to_lab(r, g, b)),
+        None => border_key_color(&img),
+    };
+    let Some(key) = key else { return img; };
+    remove_background(
+        &mut img,
+        mode,
+        key,
+        req.background_tolerance.unwrap_or(12.0),
+        req.background_feather.unwrap_or(0.0),
+    );
+    img
+}
+
+fn apply_night_vision_prefilter(img: &Rgba32FImage, enabled: bool) -> Rgba32FImage {
     if !enabled { return img.clone(); }
     // Convert to luma, boost green channel, suppress red/blue; mild blur
-    let mut rgba = img.to_rgba8();
+    let mut rgba = img.clone();
     for p in rgba.pixels_mut() {
         let [r, g, b, a] = p.0;
-        let y = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
-        let ng = (y * 1.25).clamp(0.0, 255.0) as u8;
-        *p = Rgba([0, ng, 0, a]);
+        let y = 0.299 * r + 0.587 * g + 0.114 * b;
+        *p = Rgba([0.0, (y * 1.25).clamp(0.0, 1.0), 0.0, a]);
     }
-    let blurred = image::imageops::blur(&rgba, 0.6);
-    DynamicImage::ImageRgba8(blurred)
+    image::imageops::blur(&rgba, 0.6)
 }
 
-fn apply_invert_prefilter(img: &DynamicImage, enabled: bool) -> DynamicImage {
+fn apply_invert_prefilter(img: &Rgba32FImage, enabled: bool) -> Rgba32FImage {
     if !enabled { return img.clone(); }
-    let mut rgba = img.to_rgba8();
+    let mut rgba = img.clone();
     for p in rgba.pixels_mut() {
         let [r, g, b, a] = p.0;
-        *p = Rgba([255 - r, 255 - g, 255 - b, a]);
+        *p = Rgba([1.0 - r, 1.0 - g, 1.0 - b, a]);
     }
-    DynamicImage::ImageRgba8(rgba)
+    rgba
 }
 
 // (removed deprecated preprocess; denoise is now applied after grid resize)
 
-fn apply_tone_gamma(img: &mut RgbaImage, tone_gamma: Option<f32>) {
-    if let Some(g) = tone_gamma {
-        if (g - 1.0).abs() > 0.001 {
-            let inv = 1.0 / g.max(0.05);
-            for p in img.pixels_mut() {
-                let [r, gc, b, a] = p.0;
-                let cr = ((r as f32 / 255.0).powf(inv) * 255.0).clamp(0.0, 255.0) as u8;
-                let cg = ((gc as f32 / 255.0).powf(inv) * 255.0).clamp(0.0, 255.0) as u8;
-                let cb = ((b as f32 / 255.0).powf(inv) * 255.0).clamp(0.0, 255.0) as u8;
-                *p = Rgba([cr, cg, cb, a]);
-            }
-        }
+// Tone stage: auto levels from the source histogram, levels, spline curves, then gamma
+fn apply_tone_stage(img: &mut RgbaImage, source: &Rgba32FImage, req: &RenderRequest) {
+    let auto = if req.auto_levels.unwrap_or(false) { Some(auto_levels(source)) } else { None };
+    let lut = build_tone_lut(auto, req.levels.as_ref(), req.tone_curves.as_ref(), req.tone_gamma);
+    if !lut.is_identity() {
+        lut.apply(img);
     }
 }
 
@@ -182,6 +282,43 @@ fn apply_denoise_rgba(img: RgbaImage, denoise_sigma: Option<f32>) -> RgbaImage {
     img
 }
 
+fn apply_edge_preserving_prefilter(img: &mut RgbaImage, req: &RenderRequest) {
+    let Some(filter) = req.edge_preserve_filter.as_deref().and_then(EdgePreservingFilter::from_name) else {
+        return;
+    };
+    apply_edge_preserving(
+        img,
+        filter,
+        req.edge_preserve_radius.unwrap_or(2),
+        req.edge_preserve_strength.unwrap_or(0.3),
+    );
+}
+
+fn apply_sharpen(img: &mut RgbaImage, req: &RenderRequest) {
+    let mut work = DynamicImage::ImageRgba8(std::mem::take(img)).to_rgba32f();
+    sharpen_working(&mut work, req);
+    *img = quantize_to_rgba8(work);
+}
+
+fn sharpen_working(img: &mut Rgba32FImage, req: &RenderRequest) {
+    unsharp_mask(
+        img,
+        req.sharpen_radius.unwrap_or(1.0),
+        req.sharpen_amount.unwrap_or(0.0),
+        req.sharpen_threshold.unwrap_or(0),
+    );
+    laplacian_boost(img, req.edge_boost.unwrap_or(0.0));
+}
+
+fn apply_sharpen_source(mut img: Rgba32FImage, req: &RenderRequest) -> Rgba32FImage {
+    let enabled = req.sharpen_amount.unwrap_or(0.0) > 0.0 || req.edge_boost.unwrap_or(0.0) > 0.0;
+    if !enabled || !SharpenStage::from_name(req.sharpen_stage.as_deref()).includes_source() {
+        return img;
+    }
+    sharpen_working(&mut img, req);
+    img
+}
+
 fn parse_grid_value(value: &str) -> Option<(u32, u32)> {
     let s = value.trim().to_lowercase();
     if s.is_empty() {
@@ -204,52 +341,202 @@ fn parse_grid_value(value: &str) -> Option<(u32, u32)> {
     None
 }
 
-fn resolve_grid(req: &RenderRequest) -> (u32, u32) {
+enum GridSpec {
+    Fixed(u32, u32),
+    Native(NativeGrid),
+    Hardware(u32, u32),
+}
+
+// Resolved on the decoded source, ahead of the prefilters: blurs and sharpening would
+// smear the hard pixel edges that native grid detection looks for
+fn resolve_grid(req: &RenderRequest, img: &Rgba32FImage) -> Result<GridSpec, EngineError> {
+    // Most hardware modes have a fixed screen resolution (printers a fixed width)
+    if let Some((w, h)) = hardware_mode(req).and_then(|m| m.grid_size(img.dimensions())) {
+        return Ok(GridSpec::Hardware(w, h));
+    }
     if let Some(ref gv) = req.grid_value {
+        // "auto" recovers the native pixel grid of already-upscaled pixel art
+        if gv.trim().eq_ignore_ascii_case("auto") {
+            return detect_native_grid(img).map(GridSpec::Native).ok_or(EngineError::NoNativeGrid);
+        }
         if let Some((w, h)) = parse_grid_value(gv) {
-            return (w, h);
+            return Ok(GridSpec::Fixed(w, h));
         }
     }
     // Fallback: use explicit grid_width/grid_height if both are > 0, otherwise default to 32x32
     let w = if req.grid_width > 0 { req.grid_width } else { 32 };
     let h = if req.grid_height > 0 { req.grid_height } else { 32 };
-    (w, h)
+    Ok(GridSpec::Fixed(w, h))
+}
+
+fn pixel_aspect(req: &RenderRequest) -> f32 {
+    if let Some(par) = hardware_mode(req).and_then(|m| m.pixel_aspect()) {
+        return par;
+    }
+    match req.pixel_aspect {
+        Some(par) if par.is_finite() && par > 0.0 => par.clamp(0.25, 4.0),
+        _ => 1.0,
+    }
+}
+
+// Display aspect of one grid cell; a native grid keeps the pixel shape of its lattice
+fn grid_pixel_aspect(req: &RenderRequest, spec: &GridSpec) -> f32 {
+    match spec {
+        GridSpec::Native(native) => native.x.scale as f32 / native.y.scale as f32,
+        _ => pixel_aspect(req),
+    }
+}
+
+// The requested grid describes the on-screen footprint in square units; wide pixels
+// (aspect > 1) need fewer logical columns, tall pixels (aspect < 1) fewer logical rows
+fn logical_grid_size(gw: u32, gh: u32, pixel_aspect: f32) -> (u32, u32) {
+    if pixel_aspect > 1.0 {
+        (((gw as f32 / pixel_aspect).round() as u32).max(1), gh)
+    } else if pixel_aspect < 1.0 {
+        (gw, ((gh as f32 * pixel_aspect).round() as u32).max(1))
+    } else {
+        (gw, gh)
+    }
+}
+
+fn hardware_mode(req: &RenderRequest) -> Option<HardwareMode> {
+    HardwareMode::from_request(
+        req.hardware_mode.as_deref(),
+        req.hardware_palette.as_deref(),
+        req.hardware_output.as_deref(),
+    )
+}
+
+fn source_to_grid(req: &RenderRequest, img: &Rgba32FImage, spec: &GridSpec) -> RgbaImage {
+    let grid = match spec {
+        GridSpec::Fixed(gw, gh) => {
+            let (lw, lh) = logical_grid_size(*gw, *gh, pixel_aspect(req));
+            resize_to_grid(img, lw, lh)
+        }
+        GridSpec::Native(native) => resample_native(img, native),
+        GridSpec::Hardware(w, h) => resize_to_grid(img, *w, *h),
+    };
+    quantize_to_rgba8(grid)
 }
 
-fn upscale_center_to(img: &RgbaImage, display_size: u32) -> RgbaImage {
+// Largest ratio error (as |ln|) for integer factors to count as matching the pixel aspect
+const ASPECT_TOLERANCE: f32 = 0.02;
+
+fn upscale_center_to(img: &RgbaImage, display_size: u32, pixel_aspect: f32) -> RgbaImage {
     // Whole-integer up when possible; if the image already exceeds the target,
     // downscale proportionally to fit within display_size.
+    // Non-square pixels scale each axis separately so the result looks right on screen.
     let max_dim = display_size.max(1);
-    let (w, h) = (img.width(), img.height());
-    let (target_w, target_h) = if w <= max_dim && h <= max_dim {
-        let factor_w = (max_dim / w).max(1);
-        let factor_h = (max_dim / h).max(1);
-        let factor = factor_w.min(factor_h).max(1);
-        (w * factor, h * factor)
+    let (w, h) = (img.width().max(1), img.height().max(1));
+    let (ax, ay) = if pixel_aspect >= 1.0 { (pixel_aspect, 1.0) } else { (1.0, 1.0 / pixel_aspect) };
+    // Largest integer factor pair whose ratio matches the aspect; factors grow with fy, so
+    // the last match is the biggest
+    let mut exact = None;
+    for fy in 1..=max_dim / h {
+        let fx = (fy as f32 * pixel_aspect).round() as u32;
+        if fx == 0 || w * fx > max_dim {
+            continue;
+        }
+        if ((fx as f32 / fy as f32) / pixel_aspect).ln().abs() <= ASPECT_TOLERANCE {
+            exact = Some((fx, fy));
+        }
+    }
+    // Otherwise keep an integer factor on the square axis and stretch the other one
+    let k = (max_dim as f32 / (w as f32 * ax)).min(max_dim as f32 / (h as f32 * ay)).floor();
+    let (target_w, target_h) = if let Some((fx, fy)) = exact {
+        (w * fx, h * fy)
+    } else if k >= 1.0 {
+        ((w as f32 * k * ax).round() as u32, (h as f32 * k * ay).round() as u32)
     } else {
-        let scale = (max_dim as f32 / w as f32).min(max_dim as f32 / h as f32);
-        let tw = (w as f32 * scale).floor().max(1.0) as u32;
-        let th = (h as f32 * scale).floor().max(1.0) as u32;
+        let (dw, dh) = (w as f32 * ax, h as f32 * ay);
+        let scale = (max_dim as f32 / dw).min(max_dim as f32 / dh);
+        let tw = (dw * scale).floor().max(1.0) as u32;
+        let th = (dh * scale).floor().max(1.0) as u32;
         (tw, th)
     };
     image::imageops::resize(img, target_w, target_h, FilterType::Nearest)
 }
 
+// Optional pixel-art scaler first, at the largest requested factor that still fits the
+// display size, then the usual integer nearest fit
+fn upscale_for_display(img: &RgbaImage, display_size: u32, aspect: f32, req: &RenderRequest) -> RgbaImage {
+    let scaler = req
+        .export_filter
+        .as_deref()
+        .and_then(|name| PixelScaler::from_name(name, req.export_scale_factor.unwrap_or(2)));
+    if let Some((scaler, factor)) = scaler {
+        let (ax, ay) = if aspect >= 1.0 { (aspect, 1.0) } else { (1.0, 1.0 / aspect) };
+        let footprint = (img.width() as f32 * ax).max(img.height() as f32 * ay).max(1.0);
+        let fit = (display_size as f32 / footprint).floor() as u32;
+        if fit >= 2 {
+            let scaled = upscale_pixel_art(img, scaler, factor.min(fit));
+            return upscale_center_to(&scaled, display_size, aspect);
+        }
+    }
+    upscale_center_to(img, display_size, aspect)
+}
+
 fn encode_png_base64(img: &RgbaImage) -> Result<String, EngineError> {
     // Pre-allocate buffer with estimated size (width * height * 4 * 1.5 for PNG compression overhead)
     let estimated_size = (img.width() as usize * img.height() as usize * 4 * 3) / 2;
     let mut buf = Cursor::new(Vec::with_capacity(estimated_size));
     DynamicImage::ImageRgba8(img.clone()).write_to(&mut buf, ImageFormat::Png)?;
-    let png_bytes = buf.into_inner();
-    
+    Ok(png_bytes_to_data_url(&buf.into_inner()))
+}
+
+fn png_bytes_to_data_url(png_bytes: &[u8]) -> String {
+    bytes_to_data_url(png_bytes, "image/png")
+}
+
+fn bytes_to_data_url(bytes: &[u8], mime: &str) -> String {
     // Pre-allocate base64 string with estimated size (4/3 of input size)
-    let b64_capacity = (png_bytes.len() * 4 + 2) / 3;
+    let b64_capacity = (bytes.len() * 4 + 2) / 3;
     use base64::engine::general_purpose::STANDARD as B64;
     use base64::Engine;
-    let mut b64 = String::with_capacity(b64_capacity + 22); // +22 for "data:image/png;base64," prefix
-    b64.push_str("data:image/png;base64,");
-    B64.encode_string(&png_bytes, &mut b64);
-    Ok(b64)
+    let prefix = format!("data:{mime};base64,");
+    let mut b64 = String::with_capacity(b64_capacity + prefix.len());
+    b64.push_str(&prefix);
+    B64.encode_string(bytes, &mut b64);
+    b64
+}
+
+// Base export: indexed PNG (palette order, optional transparent index 0) when requested
+// and the image fits in 256 entries; RGBA PNG otherwise
+fn encode_base_png(img: &RgbaImage, palette: &[[u8; 3]], req: &RenderRequest) -> Result<String, EngineError> {
+    if req.indexed_export.unwrap_or(false) {
+        let transparent_slot = req.transparent_palette_slot.unwrap_or(false);
+        if let Some(bytes) = encode_indexed_png(img, palette, transparent_slot)? {
+            return Ok(png_bytes_to_data_url(&bytes));
+        }
+    }
+    encode_png_base64(img)
+}
+
+// Post-processes at grid resolution, after dithering: cleanup, then outline
+fn apply_cleanup_pass(img: &mut RgbaImage, req: &RenderRequest) {
+    let strength = req.cleanup_strength.unwrap_or(0.0);
+    if strength <= 0.0 { return; }
+    let protected: Vec<[u8; 3]> = req
+        .cleanup_protected_colors
+        .iter()
+        .flatten()
+        .filter_map(|hex| hex_to_rgb(hex))
+        .collect();
+    apply_cleanup(img, strength, &protected);
+}
+
+fn apply_outline_pass(img: &mut RgbaImage, palette: &[[u8; 3]], req: &RenderRequest) {
+    let color = req.outline_palette_index.and_then(|i| palette.get(i).copied());
+    let Some(outline) = Outline::from_request(
+        req.outline.as_deref(),
+        req.outline_source.as_deref(),
+        req.outline_threshold,
+        color,
+        req.outline_connectivity,
+    ) else {
+        return;
+    };
+    apply_outline(img, outline, palette);
 }
 
 fn maybe_modify_palette(colors: &mut Vec<[u8; 3]>, add_black: bool, add_white: bool) {
@@ -266,87 +553,90 @@ fn maybe_modify_palette(colors: &mut Vec<[u8; 3]>, add_black: bool, add_white: b
     }
 }
 
-pub fn render_preview_png(req: RenderRequest) -> Result<String, EngineError> {
-    let img0 = decode_data_url_to_image(&req.image_data_url)?;
+// Grid-resolution result shared by the preview, base and hardware exports
+struct RenderedGrid {
+    grid: RgbaImage,
+    palette: Vec<[u8; 3]>,
+    pixel_aspect: f32,
+    files: Vec<HardwareFile>,
+    warnings: Vec<String>,
+}
+
+// The grid before quantization, the prefiltered source it was sampled from, and the
+// display aspect of its cells
+struct PreparedGrid {
+    grid: RgbaImage,
+    source: Rgba32FImage,
+    pixel_aspect: f32,
+}
+
+// Prefilters, grid resampling and tone
+fn prepare_grid(req: &RenderRequest) -> Result<PreparedGrid, EngineError> {
+    let img0 = apply_transform_stage(decode_working_image(req)?, req);
+    let spec = resolve_grid(req, &img0)?;
+    let img0 = apply_background_prefilter(img0, req);
     // Optional prefilters: invert then night vision, then color pre-adjust
     let inv = apply_invert_prefilter(&img0, req.invert_colors.unwrap_or(false));
     let night = apply_night_vision_prefilter(&inv, req.night_vision_prefilter.unwrap_or(false));
     let img = apply_pre_color_adjustments(&night, req.pre_contrast, req.pre_saturation, req.pre_hue_degrees);
-    let (gw, gh) = resolve_grid(&req);
-    let mut grid = resize_to_grid(&img, gw, gh);
+    let img = apply_lut_prefilter(img, req)?;
+    let img = apply_color_transfer_prefilter(img, req)?;
+    let img = apply_equalize_prefilter(img, req);
+    let img = apply_sharpen_source(img, req);
+    let mut grid = source_to_grid(req, &img, &spec);
     grid = apply_denoise_rgba(grid, req.denoise_sigma);
-    apply_tone_gamma(&mut grid, req.tone_gamma);
-    let algo = get_algorithm_by_name(req.algorithm.as_str());
-    let palette_name = req.palette_name.as_deref().unwrap_or("Flying Tiger");
-    let palette = get_palette_by_name(palette_name);
-    let mut pal_slice: Vec<[u8; 3]> = palette.colors.clone();
-    let add_black = req.add_black_to_palette.unwrap_or(false);
-    let add_white = req.add_white_to_palette.unwrap_or(false);
-    if add_black || add_white {
-        maybe_modify_palette(&mut pal_slice, add_black, add_white);
-    }
-    match req.algorithm.as_str() {
-        "Floyd-Steinberg" | "Floyd–Steinberg" => FloydSteinberg.process(&mut grid, &pal_slice),
-        "Bayer" => Bayer.process(&mut grid, &pal_slice),
-        "Bayer 2x2" => Bayer2.process(&mut grid, &pal_slice),
-        "Bayer 8x8" => Bayer8.process(&mut grid, &pal_slice),
-        "Selective" => apply_selective(&mut grid, &pal_slice, 25.0),
-        "Ordered Selective" => apply_ordered_selective(&mut grid, &pal_slice, 25.0),
-        "Dual Color Dithering" => apply_dual_color(&mut grid, &pal_slice),
-        "Edge Dithering" => apply_edge_dithering(&mut grid, &pal_slice),
-        "Randomized Selective" => apply_randomized_selective(&mut grid, &pal_slice, 30.0),
-        "Stucki" => apply_stucki(&mut grid, &pal_slice),
-        "Atkinson" => apply_atkinson(&mut grid, &pal_slice),
-        "Jarvis-Judice-Ninke" | "Jarvis, Judice, and Ninke" => apply_jjn(&mut grid, &pal_slice),
-        "Burkes" => apply_burkes(&mut grid, &pal_slice),
-        "Sierra" => apply_sierra(&mut grid, &pal_slice),
-        "Two-Row Sierra" => apply_two_row_sierra(&mut grid, &pal_slice),
-        "Sierra Lite" => apply_sierra_lite(&mut grid, &pal_slice),
-        _ => algo.process(&mut grid, &pal_slice),
+    apply_edge_preserving_prefilter(&mut grid, req);
+    if SharpenStage::from_name(req.sharpen_stage.as_deref()).includes_grid() {
+        apply_sharpen(&mut grid, req);
     }
-    let target = req.display_size.unwrap_or(560);
-    let up = upscale_center_to(&grid, target);
-    encode_png_base64(&up)
+    apply_tone_stage(&mut grid, &img, req);
+    apply_alpha_mode(&mut grid, AlphaMode::from_request(req.alpha_mode.as_deref(), req.alpha_threshold));
+    Ok(PreparedGrid { grid, source: img, pixel_aspect: grid_pixel_aspect(req, &spec) })
 }
 
-pub fn render_base_png(req: RenderRequest) -> Result<String, EngineError> {
-    let img0 = decode_data_url_to_image(&req.image_data_url)?;
-    let inv = apply_invert_prefilter(&img0, req.invert_colors.unwrap_or(false));
-    let night = apply_night_vision_prefilter(&inv, req.night_vision_prefilter.unwrap_or(false));
-    let img = apply_pre_color_adjustments(&night, req.pre_contrast, req.pre_saturation, req.pre_hue_degrees);
-    let (gw, gh) = resolve_grid(&req);
-    let mut grid = resize_to_grid(&img, gw, gh);
-    grid = apply_denoise_rgba(grid, req.denoise_sigma);
-    apply_tone_gamma(&mut grid, req.tone_gamma);
-    let algo = get_algorithm_by_name(req.algorithm.as_str());
-    let palette_name = req.palette_name.as_deref().unwrap_or("Flying Tiger");
-    let palette = get_palette_by_name(palette_name);
-    let mut pal_slice: Vec<[u8; 3]> = palette.colors.clone();
+// Quantization and post-processing. Hardware modes replace the palette and quantization
+// with their own constrained renderer.
+fn quantize_grid(
+    req: &RenderRequest,
+    mut grid: RgbaImage,
+    pixel_aspect: f32,
+    palette_colors: Vec<[u8; 3]>,
+) -> RenderedGrid {
+    if let Some(mode) = hardware_mode(req) {
+        // Cleanup and outline would break the hardware's color constraints
+        let frame = render_hardware(mode, &mut grid, req.algorithm.as_str(), &palette_colors);
+        return RenderedGrid { grid, palette: frame.palette, pixel_aspect, files: frame.files, warnings: frame.warnings };
+    }
+    let mut pal_slice: Vec<[u8; 3]> = palette_colors;
     let add_black = req.add_black_to_palette.unwrap_or(false);
     let add_white = req.add_white_to_palette.unwrap_or(false);
     if add_black || add_white {
         maybe_modify_palette(&mut pal_slice, add_black, add_white);
     }
-    match req.algorithm.as_str() {
-        "Floyd-Steinberg" | "Floyd–Steinberg" => FloydSteinberg.process(&mut grid, &pal_slice),
-        "Bayer" => Bayer.process(&mut grid, &pal_slice),
-        "Bayer 2x2" => Bayer2.process(&mut grid, &pal_slice),
-        "Bayer 8x8" => Bayer8.process(&mut grid, &pal_slice),
-        "Selective" => apply_selective(&mut grid, &pal_slice, 25.0),
-        "Ordered Selective" => apply_ordered_selective(&mut grid, &pal_slice, 25.0),
-        "Dual Color Dithering" => apply_dual_color(&mut grid, &pal_slice),
-        "Edge Dithering" => apply_edge_dithering(&mut grid, &pal_slice),
-        "Randomized Selective" => apply_randomized_selective(&mut grid, &pal_slice, 30.0),
-        "Stucki" => apply_stucki(&mut grid, &pal_slice),
-        "Atkinson" => apply_atkinson(&mut grid, &pal_slice),
-        "Jarvis-Judice-Ninke" | "Jarvis, Judice, and Ninke" => apply_jjn(&mut grid, &pal_slice),
-        "Burkes" => apply_burkes(&mut grid, &pal_slice),
-        "Sierra" => apply_sierra(&mut grid, &pal_slice),
-        "Two-Row Sierra" => apply_two_row_sierra(&mut grid, &pal_slice),
-        "Sierra Lite" => apply_sierra_lite(&mut grid, &pal_slice),
-        _ => algo.process(&mut grid, &pal_slice),
-    }
-    encode_png_base64(&grid)
+    apply_dither_by_name(&mut grid, req.algorithm.as_str(), &pal_slice);
+    apply_cleanup_pass(&mut grid, req);
+    apply_outline_pass(&mut grid, &pal_slice, req);
+    RenderedGrid { grid, palette: pal_slice, pixel_aspect, files: Vec::new(), warnings: Vec::new() }
+}
+
+fn render_grid(req: &RenderRequest, palette_colors: Vec<[u8; 3]>) -> Result<RenderedGrid, EngineError> {
+    let prepared = prepare_grid(req)?;
+    Ok(quantize_grid(req, prepared.grid, prepared.pixel_aspect, palette_colors))
+}
+
+fn builtin_palette(req: &RenderRequest) -> Vec<[u8; 3]> {
+    let palette_name = req.palette_name.as_deref().unwrap_or("Flying Tiger");
+    get_palette_by_name(palette_name).colors.clone()
+}
+
+pub fn render_preview_png(req: RenderRequest) -> Result<String, EngineError> {
+    let palette_colors = builtin_palette(&req);
+    render_preview_png_with_palette(req, palette_colors)
+}
+
+pub fn render_base_png(req: RenderRequest) -> Result<String, EngineError> {
+    let palette_colors = builtin_palette(&req);
+    render_base_png_with_palette(req, palette_colors)
 }
 
 // Versions that accept explicit palette colors (e.g., from GPL) to avoid relying on built-ins
@@ -354,42 +644,9 @@ pub fn render_preview_png_with_palette(
     req: RenderRequest,
     palette_colors: Vec<[u8; 3]>,
 ) -> Result<String, EngineError> {
-    let img0 = decode_data_url_to_image(&req.image_data_url)?;
-    let inv = apply_invert_prefilter(&img0, req.invert_colors.unwrap_or(false));
-    let night = apply_night_vision_prefilter(&inv, req.night_vision_prefilter.unwrap_or(false));
-    let img = apply_pre_color_adjustments(&night, req.pre_contrast, req.pre_saturation, req.pre_hue_degrees);
-    let (gw, gh) = resolve_grid(&req);
-    let mut grid = resize_to_grid(&img, gw, gh);
-    grid = apply_denoise_rgba(grid, req.denoise_sigma);
-    apply_tone_gamma(&mut grid, req.tone_gamma);
-    let algo = get_algorithm_by_name(req.algorithm.as_str());
-    let mut pal_slice: Vec<[u8; 3]> = palette_colors;
-    let add_black = req.add_black_to_palette.unwrap_or(false);
-    let add_white = req.add_white_to_palette.unwrap_or(false);
-    if add_black || add_white {
-        maybe_modify_palette(&mut pal_slice, add_black, add_white);
-    }
-    match req.algorithm.as_str() {
-        "Floyd-Steinberg" | "Floyd–Steinberg" => FloydSteinberg.process(&mut grid, &pal_slice),
-        "Bayer" => Bayer.process(&mut grid, &pal_slice),
-        "Bayer 2x2" => Bayer2.process(&mut grid, &pal_slice),
-        "Bayer 8x8" => Bayer8.process(&mut grid, &pal_slice),
-        "Selective" => apply_selective(&mut grid, &pal_slice, 25.0),
-        "Ordered Selective" => apply_ordered_selective(&mut grid, &pal_slice, 25.0),
-        "Dual Color Dithering" => apply_dual_color(&mut grid, &pal_slice),
-        "Edge Dithering" => apply_edge_dithering(&mut grid, &pal_slice),
-        "Randomized Selective" => apply_randomized_selective(&mut grid, &pal_slice, 30.0),
-        "Stucki" => apply_stucki(&mut grid, &pal_slice),
-        "Atkinson" => apply_atkinson(&mut grid, &pal_slice),
-        "Jarvis-Judice-Ninke" | "Jarvis, Judice, and Ninke" => apply_jjn(&mut grid, &pal_slice),
-        "Burkes" => apply_burkes(&mut grid, &pal_slice),
-        "Sierra" => apply_sierra(&mut grid, &pal_slice),
-        "Two-Row Sierra" => apply_two_row_sierra(&mut grid, &pal_slice),
-        "Sierra Lite" => apply_sierra_lite(&mut grid, &pal_slice),
-        _ => algo.process(&mut grid, &pal_slice),
-    }
+    let rendered = render_grid(&req, palette_colors)?;
     let target = req.display_size.unwrap_or(560);
-    let up = upscale_center_to(&grid, target);
+    let up = upscale_for_display(&rendered.grid, target, rendered.pixel_aspect, &req);
     encode_png_base64(&up)
 }
 
@@ -397,39 +654,51 @@ pub fn render_base_png_with_palette(
     req: RenderRequest,
     palette_colors: Vec<[u8; 3]>,
 ) -> Result<String, EngineError> {
-    let img0 = decode_data_url_to_image(&req.image_data_url)?;
-    let inv = apply_invert_prefilter(&img0, req.invert_colors.unwrap_or(false));
-    let night = apply_night_vision_prefilter(&inv, req.night_vision_prefilter.unwrap_or(false));
-    let img = apply_pre_color_adjustments(&night, req.pre_contrast, req.pre_saturation, req.pre_hue_degrees);
-    let (gw, gh) = resolve_grid(&req);
-    let mut grid = resize_to_grid(&img, gw, gh);
-    grid = apply_denoise_rgba(grid, req.denoise_sigma);
-    apply_tone_gamma(&mut grid, req.tone_gamma);
-    let algo = get_algorithm_by_name(req.algorithm.as_str());
-    let mut pal_slice: Vec<[u8; 3]> = palette_colors;
-    let add_black = req.add_black_to_palette.unwrap_or(false);
-    let add_white = req.add_white_to_palette.unwrap_or(false);
-    if add_black || add_white {
-        maybe_modify_palette(&mut pal_slice, add_black, add_white);
-    }
-    match req.algorithm.as_str() {
-        "Floyd-Steinberg" | "Floyd–Steinberg" => FloydSteinberg.process(&mut grid, &pal_slice),
-        "Bayer" => Bayer.process(&mut grid, &pal_slice),
-        "Bayer 2x2" => Bayer2.process(&mut grid, &pal_slice),
-        "Bayer 8x8" => Bayer8.process(&mut grid, &pal_slice),
-        "Selective" => apply_selective(&mut grid, &pal_slice, 25.0),
-        "Ordered Selective" => apply_ordered_selective(&mut grid, &pal_slice, 25.0),
-        "Dual Color Dithering" => apply_dual_color(&mut grid, &pal_slice),
-        "Edge Dithering" => apply_edge_dithering(&mut grid, &pal_slice),
-        "Randomized Selective" => apply_randomized_selective(&mut grid, &pal_slice, 30.0),
-        "Stucki" => apply_stucki(&mut grid, &pal_slice),
-        "Atkinson" => apply_atkinson(&mut grid, &pal_slice),
-        "Jarvis-Judice-Ninke" | "Jarvis, Judice, and Ninke" => apply_jjn(&mut grid, &pal_slice),
-        "Burkes" => apply_burkes(&mut grid, &pal_slice),
-        "Sierra" => apply_sierra(&mut grid, &pal_slice),
-        "Two-Row Sierra" => apply_two_row_sierra(&mut grid, &pal_slice),
-        "Sierra Lite" => apply_sierra_lite(&mut grid, &pal_slice),
-        _ => algo.process(&mut grid, &pal_slice),
+    let rendered = render_grid(&req, palette_colors)?;
+    encode_base_png(&rendered.grid, &rendered.palette, &req)
+}
+
+/// Hardware-mode export: the base PNG plus the target's native files (e.g. a ZX Spectrum
+/// .scr), each as a data URL, and any hardware-limit warnings. Errors when `hardware_mode`
+/// is unset or unknown.
+pub fn render_hardware_export(
+    req: RenderRequest,
+    palette_colors: Vec<[u8; 3]>,
+) -> Result<HardwareExport, EngineError> {
+    if hardware_mode(&req).is_none() {
+        return Err(EngineError::NoHardwareMode);
     }
-    encode_png_base64(&grid)
+    let rendered = render_grid(&req, palette_colors)?;
+    let png_data_url = encode_base_png(&rendered.grid, &rendered.palette, &req)?;
+    let files = rendered
+        .files
+        .into_iter()
+        .map(|f| ExportedFile { data_url: bytes_to_data_url(&f.bytes, "application/octet-stream"), file_name: f.file_name })
+        .collect();
+    Ok(HardwareExport { png_data_url, files, warnings: rendered.warnings })
+}
+
+/// Text render: one character per grid cell, chosen from `text_charset` by matching glyph
+/// coverage and shape in the built-in 8x8 font, colored from the quantized grid and written
+/// as `text_format` (plain ASCII, ANSI 16/256/truecolor or HTML). Cells default to twice as
+/// tall as wide, like terminal characters, unless `pixel_aspect` is given.
+pub fn render_text_output(req: RenderRequest) -> Result<String, EngineError> {
+    let palette_colors = builtin_palette(&req);
+    render_text_output_with_palette(req, palette_colors)
+}
+
+pub fn render_text_output_with_palette(
+    mut req: RenderRequest,
+    palette_colors: Vec<[u8; 3]>,
+) -> Result<String, EngineError> {
+    req.pixel_aspect = req.pixel_aspect.or(Some(0.5));
+    let PreparedGrid { grid, source, pixel_aspect } = prepare_grid(&req)?;
+    let rendered = quantize_grid(&req, grid, pixel_aspect, palette_colors);
+    // Glyph matching samples the source at font resolution, with the grid's tone adjustments
+    let (columns, rows) = rendered.grid.dimensions();
+    let mut detail = quantize_to_rgba8(image::imageops::resize(&source, columns * CELL, rows * CELL, FilterType::Triangle));
+    apply_tone_stage(&mut detail, &source, &req);
+    let charset = req.text_charset.as_deref().unwrap_or(DEFAULT_CHARSET);
+    let text = match_glyphs(&rendered.grid, &detail, charset, req.text_invert.unwrap_or(false));
+    Ok(encode_text(&text, TextFormat::from_name(req.text_format.as_deref())))
 }
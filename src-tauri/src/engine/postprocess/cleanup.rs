@@ -0,0 +1,130 @@
+use crate::engine::algorithms::RgbaImage;
+
+const NEIGHBOURS_8: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+// An isolated pixel with at least this many same-colored isolated pixels in its 5x5
+// window is taken to be part of a dither pattern
+const PATTERN_PEERS: usize = 2;
+// Of the six other neighbours of an L-corner, this many must share the backdrop color
+const CORNER_BACKDROP: usize = 5;
+// Corner (jaggy) fixes need at least this strength
+const JAGGY_MIN_STRENGTH: f32 = 0.5;
+
+type Px = [u8; 4];
+
+// Fully transparent pixels compare equal regardless of their color channels
+#[inline]
+fn same(a: Px, b: Px) -> bool {
+    (a[3] == 0 && b[3] == 0) || a == b
+}
+
+struct View<'a> {
+    img: &'a RgbaImage,
+    w: i32,
+    h: i32,
+}
+
+impl View<'_> {
+    fn at(&self, x: i32, y: i32) -> Option<Px> {
+        if x < 0 || y < 0 || x >= self.w || y >= self.h { return None; }
+        Some(self.img.get_pixel(x as u32, y as u32).0)
+    }
+
+    // No 4-neighbour shares the pixel's color
+    fn isolated(&self, x: i32, y: i32) -> bool {
+        let Some(p) = self.at(x, y) else { return false; };
+        [(-1, 0), (1, 0), (0, -1), (0, 1)]
+            .iter()
+            .all(|&(dx, dy)| self.at(x + dx, y + dy).is_none_or(|q| !same(p, q)))
+    }
+
+    fn in_dither_pattern(&self, x: i32, y: i32) -> bool {
+        let Some(p) = self.at(x, y) else { return false; };
+        let mut peers = 0;
+        for dy in -2..=2 {
+            for dx in -2..=2 {
+                if dx == 0 && dy == 0 { continue; }
+                let (nx, ny) = (x + dx, y + dy);
+                if self.at(nx, ny).is_some_and(|q| same(p, q)) && self.isolated(nx, ny) {
+                    peers += 1;
+                }
+            }
+        }
+        peers >= PATTERN_PEERS
+    }
+
+    // Most common 8-neighbour color other than `except`, and its count
+    fn majority(&self, x: i32, y: i32, except: Option<Px>) -> Option<(Px, usize)> {
+        let mut counts: Vec<(Px, usize)> = Vec::with_capacity(8);
+        for (dx, dy) in NEIGHBOURS_8 {
+            let Some(q) = self.at(x + dx, y + dy) else { continue; };
+            if except.is_some_and(|e| same(e, q)) { continue; }
+            match counts.iter_mut().find(|(c, _)| same(*c, q)) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((q, 1)),
+            }
+        }
+        counts.into_iter().max_by_key(|(_, n)| *n)
+    }
+
+    // Replacement for a redundant L-corner: a pixel whose same-colored neighbours are one
+    // horizontal and one vertical neighbour, plus at most the outer diagonals next to those
+    // arms, with the diagonal between the arms open. Removing it keeps the line 8-connected.
+    fn corner_fix(&self, x: i32, y: i32) -> Option<Px> {
+        let p = self.at(x, y)?;
+        if p[3] == 0 { return None; }
+        let matches = |(dx, dy): (i32, i32)| self.at(x + dx, y + dy).is_some_and(|q| same(p, q));
+        let horizontal: Vec<i32> = [-1, 1].into_iter().filter(|&dx| matches((dx, 0))).collect();
+        let vertical: Vec<i32> = [-1, 1].into_iter().filter(|&dy| matches((0, dy))).collect();
+        let (&[hx], &[vy]) = (&horizontal[..], &vertical[..]) else { return None; };
+        let (hz, vt) = ((hx, 0), (0, vy));
+        // Inner diagonal and the diagonal opposite it must both be open
+        if matches((hx, vy)) || matches((-hx, -vy)) { return None; }
+        // Both arms must continue away from the corner, otherwise this is a 2px stub
+        let arm_continues = |(dx, dy): (i32, i32)| {
+            let (ax, ay) = (x + dx, y + dy);
+            NEIGHBOURS_8.iter().any(|&(ex, ey)| {
+                (ax + ex, ay + ey) != (x, y) && self.at(ax + ex, ay + ey).is_some_and(|q| same(p, q))
+            })
+        };
+        if !arm_continues(hz) || !arm_continues(vt) { return None; }
+        // Only on a mostly flat backdrop; busy surroundings are texture or dithering
+        self.majority(x, y, Some(p)).filter(|(_, n)| *n >= CORNER_BACKDROP).map(|(q, _)| q)
+    }
+}
+
+/// Cleans dithered output at grid resolution. Stray single pixels are replaced by the majority
+/// neighbour color once enough neighbours agree (fewer with higher `strength`), and at
+/// `strength` >= 0.5 redundant L-shaped corners on 1px lines are removed. Isolated pixels
+/// that repeat nearby (dither patterns) and `protected` colors are never changed.
+pub fn apply_cleanup(img: &mut RgbaImage, strength: f32, protected: &[[u8; 3]]) {
+    let s = strength.clamp(0.0, 1.0);
+    if s <= 0.0 { return; }
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    let is_protected = |p: Px| p[3] > 0 && protected.iter().any(|c| c[..] == p[..3]);
+    // 7 agreeing neighbours at low strength down to 3 at full strength
+    let needed = (8.0 - s * 5.0).round().clamp(3.0, 8.0) as usize;
+
+    // Orphans, decided on the original image
+    let src = img.clone();
+    let view = View { img: &src, w, h };
+    for y in 0..h {
+        for x in 0..w {
+            let p = src.get_pixel(x as u32, y as u32).0;
+            if is_protected(p) || !view.isolated(x, y) || view.in_dither_pattern(x, y) { continue; }
+            if let Some((q, n)) = view.majority(x, y, None) {
+                if n >= needed { img.put_pixel(x as u32, y as u32, image::Rgba(q)); }
+            }
+        }
+    }
+
+    if s < JAGGY_MIN_STRENGTH { return; }
+    // Redundant L-corners, checked against the current state so both corners of a step
+    // aren't removed
+    for y in 0..h {
+        for x in 0..w {
+            if is_protected(img.get_pixel(x as u32, y as u32).0) { continue; }
+            let fix = View { img, w, h }.corner_fix(x, y);
+            if let Some(q) = fix { img.put_pixel(x as u32, y as u32, image::Rgba(q)); }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::engine::algorithms::RgbaImage;
+use crate::engine::color::{lab_distance, rgb_to_lab};
+
+// Lightness kept by the auto-darkened outline color
+const AUTO_DARKEN: f32 = 0.45;
+
+const NEIGHBOURS_4: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const NEIGHBOURS_8: [(i32, i32); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlinePlacement {
+    /// Pixels just outside the subject are painted
+    Outer,
+    /// The subject's own boundary pixels are painted
+    Inner,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlineSource {
+    /// Subject = opaque pixels, background = transparent
+    Alpha,
+    /// Subject = the brighter side of any neighbour pair differing by more than the threshold
+    Luminance(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outline {
+    pub placement: OutlinePlacement,
+    pub source: OutlineSource,
+    /// Fixed outline color; None = auto-darkened from the neighbouring subject pixels
+    pub color: Option<[u8; 3]>,
+    pub eight_connected: bool,
+}
+
+impl Outline {
+    pub fn from_request(
+        placement: Option<&str>,
+        source: Option<&str>,
+        threshold: Option<u8>,
+        color: Option<[u8; 3]>,
+        connectivity: Option<u8>,
+    ) -> Option<Self> {
+        let placement = match placement.map(|p| p.trim().to_ascii_lowercase()).as_deref() {
+            Some("outer") | Some("outside") => OutlinePlacement::Outer,
+            Some("inner") | Some("inside") => OutlinePlacement::Inner,
+            _ => return None,
+        };
+        let source = match source.map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+            Some("luminance") | Some("luma") | Some("edges") => OutlineSource::Luminance(threshold.unwrap_or(64).max(1)),
+            _ => OutlineSource::Alpha,
+        };
+        Some(Self { placement, source, color, eight_connected: connectivity == Some(8) })
+    }
+}
+
+#[inline]
+fn luma(p: [u8; 4]) -> f32 {
+    0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32
+}
+
+// Darken in Lab, then snap to the closest palette entry that is actually darker (if any)
+fn darken_to_palette(rgb: [f32; 3], palette: &[[u8; 3]]) -> [u8; 3] {
+    let lab = rgb_to_lab(rgb[0].round() as u8, rgb[1].round() as u8, rgb[2].round() as u8);
+    let target = [lab[0] * AUTO_DARKEN, lab[1], lab[2]];
+    let nearest = |darker_only: bool| {
+        palette
+            .iter()
+            .map(|c| (c, rgb_to_lab(c[0], c[1], c[2])))
+            .filter(|(_, l)| !darker_only || l[0] < lab[0])
+            .min_by(|a, b| lab_distance(a.1, target).total_cmp(&lab_distance(b.1, target)))
+            .map(|(c, _)| *c)
+    };
+    nearest(true)
+        .or_else(|| nearest(false))
+        .unwrap_or([(rgb[0] * AUTO_DARKEN) as u8, (rgb[1] * AUTO_DARKEN) as u8, (rgb[2] * AUTO_DARKEN) as u8])
+}
+
+/// Paints a 1px outline around the subject. Targets are found on the unmodified image, so
+/// the outline never feeds back into itself. Auto colors are snapped to `palette` to keep
+/// the output within the palette for indexed exports.
+pub fn apply_outline(img: &mut RgbaImage, outline: Outline, palette: &[[u8; 3]]) {
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    if w == 0 || h == 0 { return; }
+    let src = img.clone();
+    let px = |x: i32, y: i32| src.get_pixel(x as u32, y as u32).0;
+    let offsets: &[(i32, i32)] = if outline.eight_connected { &NEIGHBOURS_8 } else { &NEIGHBOURS_4 };
+    // Is (nx, ny) on the subject side relative to (x, y)?
+    let subject_of = |x: i32, y: i32, nx: i32, ny: i32| -> bool {
+        let (p, q) = (px(x, y), px(nx, ny));
+        match outline.source {
+            OutlineSource::Alpha => q[3] > 0,
+            OutlineSource::Luminance(t) => p[3] > 0 && q[3] > 0 && luma(q) - luma(p) > t as f32,
+        }
+    };
+    for y in 0..h {
+        for x in 0..w {
+            let p = px(x, y);
+            // Subject neighbours of this pixel (outer) or background neighbours (inner)
+            let mut hit = false;
+            let mut acc = [0.0f32; 3];
+            let mut n = 0u32;
+            for &(dx, dy) in offsets {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= w || ny >= h { continue; }
+                let is_target = match (outline.placement, outline.source) {
+                    (OutlinePlacement::Outer, OutlineSource::Alpha) => p[3] == 0 && subject_of(x, y, nx, ny),
+                    (OutlinePlacement::Inner, OutlineSource::Alpha) => p[3] > 0 && px(nx, ny)[3] == 0,
+                    (OutlinePlacement::Outer, OutlineSource::Luminance(_)) => subject_of(x, y, nx, ny),
+                    (OutlinePlacement::Inner, OutlineSource::Luminance(_)) => subject_of(nx, ny, x, y),
+                };
+                if !is_target { continue; }
+                hit = true;
+                let c = if outline.placement == OutlinePlacement::Outer { px(nx, ny) } else { p };
+                for ch in 0..3 { acc[ch] += c[ch] as f32; }
+                n += 1;
+            }
+            if !hit { continue; }
+            let [r, g, b] = outline.color.unwrap_or_else(|| {
+                darken_to_palette([acc[0] / n as f32, acc[1] / n as f32, acc[2] / n as f32], palette)
+            });
+            img.put_pixel(x as u32, y as u32, image::Rgba([r, g, b, 255]));
+        }
+    }
+}
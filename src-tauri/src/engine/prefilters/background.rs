@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+
+use image::Rgba32FImage;
+
+use crate::engine::color::{lab_distance, srgb01_to_lab};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundRemoval {
+    /// Every pixel close to the key color becomes transparent
+    ColorKey,
+    /// Only background connected to the image border (4-connected) becomes transparent,
+    /// so matching colors inside the subject survive
+    FloodFill,
+}
+
+impl BackgroundRemoval {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "color_key" | "color key" | "chroma_key" | "chroma key" | "key" => Some(Self::ColorKey),
+            "flood_fill" | "flood fill" | "flood" | "border" => Some(Self::FloodFill),
+            _ => None,
+        }
+    }
+}
+
+fn pixel_lab(p: [f32; 4]) -> [f32; 3] {
+    srgb01_to_lab([p[0], p[1], p[2]])
+}
+
+/// Per-channel median (in Lab) of the opaque border pixels; a stand-in for the backdrop color.
+pub fn border_key_color(img: &Rgba32FImage) -> Option<[f32; 3]> {
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 { return None; }
+    let mut samples: Vec<[f32; 3]> = Vec::with_capacity(2 * (w + h) as usize);
+    for (x, y) in border_coords(w, h) {
+        let p = img.get_pixel(x, y).0;
+        if p[3] > 0.0 { samples.push(pixel_lab(p)); }
+    }
+    if samples.is_empty() { return None; }
+    let mut key = [0.0f32; 3];
+    for (ch, k) in key.iter_mut().enumerate() {
+        let mut v: Vec<f32> = samples.iter().map(|s| s[ch]).collect();
+        v.sort_unstable_by(f32::total_cmp);
+        *k = v[v.len() / 2];
+    }
+    Some(key)
+}
+
+fn border_coords(w: u32, h: u32) -> impl Iterator<Item = (u32, u32)> {
+    let top_bottom = (0..w).flat_map(move |x| [(x, 0), (x, h - 1)]);
+    let sides = (1..h.saturating_sub(1)).flat_map(move |y| [(0, y), (w - 1, y)]);
+    top_bottom.chain(sides)
+}
+
+/// Writes background removal into the alpha channel. Pixels within `tolerance` of `key`
+/// (Lab Delta E) become fully transparent; with `feather` > 0, alpha ramps back to the
+/// original over the next `feather` units so anti-aliased edges stay soft.
+pub fn remove_background(img: &mut Rgba32FImage, mode: BackgroundRemoval, key: [f32; 3], tolerance: f32, feather: f32) {
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 { return; }
+    let tol = tolerance.max(0.0);
+    let feather = feather.max(0.0);
+    // Fraction of the original alpha to keep; 1.0 = untouched
+    let keep = |d: f32| -> f32 {
+        if d <= tol { 0.0 } else if feather > 0.0 && d < tol + feather { (d - tol) / feather } else { 1.0 }
+    };
+    let dist: Vec<f32> = img.pixels().map(|p| lab_distance(pixel_lab(p.0), key)).collect();
+    match mode {
+        BackgroundRemoval::ColorKey => {
+            for (p, &d) in img.pixels_mut().zip(dist.iter()) {
+                p.0[3] *= keep(d);
+            }
+        }
+        BackgroundRemoval::FloodFill => {
+            // Grow from the border through pixels inside the tolerance (or already transparent);
+            // feathered pixels are touched but not expanded through, so the fill stops at edges
+            let idx = |x: u32, y: u32| (y * w + x) as usize;
+            let mut visited = vec![false; (w * h) as usize];
+            let mut queue = VecDeque::new();
+            for (x, y) in border_coords(w, h) {
+                let i = idx(x, y);
+                if !visited[i] {
+                    visited[i] = true;
+                    queue.push_back((x, y));
+                }
+            }
+            while let Some((x, y)) = queue.pop_front() {
+                let i = idx(x, y);
+                let p = img.get_pixel_mut(x, y);
+                let passable = dist[i] <= tol || p.0[3] <= 0.0;
+                p.0[3] *= keep(dist[i]);
+                if !passable { continue; }
+                let neighbours = [
+                    (x.wrapping_sub(1), y),
+                    (x + 1, y),
+                    (x, y.wrapping_sub(1)),
+                    (x, y + 1),
+                ];
+                for (nx, ny) in neighbours {
+                    if nx >= w || ny >= h { continue; }
+                    let j = idx(nx, ny);
+                    if !visited[j] {
+                        visited[j] = true;
+                        queue.push_back((nx, ny));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use image::Rgba32FImage;
+
+use crate::engine::color::{lab_to_srgb01, srgb01_to_lab};
+
+// Histogram bins per Lab channel for the histogram-matching variant
+const BINS: usize = 512;
+// Channel ranges used to bin Lab values
+const LAB_RANGE: [(f32, f32); 3] = [(0.0, 100.0), (-128.0, 128.0), (-128.0, 128.0)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorTransfer {
+    /// Reinhard et al.: match per-channel mean and standard deviation in Lab
+    Reinhard,
+    /// Match each Lab channel's full distribution (CDF) to the reference
+    Histogram,
+}
+
+impl ColorTransfer {
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name.map(|n| n.trim().to_ascii_lowercase()).as_deref() {
+            Some("histogram") | Some("histogram matching") => Self::Histogram,
+            _ => Self::Reinhard,
+        }
+    }
+}
+
+fn opaque_lab(img: &Rgba32FImage) -> Vec<[f32; 3]> {
+    img.pixels()
+        .filter(|p| p.0[3] > 0.0)
+        .map(|p| srgb01_to_lab([p.0[0], p.0[1], p.0[2]]))
+        .collect()
+}
+
+fn mean_std(values: &[[f32; 3]]) -> ([f32; 3], [f32; 3]) {
+    let n = values.len().max(1) as f64;
+    let mut mean = [0.0f64; 3];
+    for v in values {
+        for ch in 0..3 { mean[ch] += v[ch] as f64; }
+    }
+    for m in mean.iter_mut() { *m /= n; }
+    let mut var = [0.0f64; 3];
+    for v in values {
+        for ch in 0..3 { var[ch] += (v[ch] as f64 - mean[ch]).powi(2); }
+    }
+    let mean = [mean[0] as f32, mean[1] as f32, mean[2] as f32];
+    let std = [(var[0] / n).sqrt() as f32, (var[1] / n).sqrt() as f32, (var[2] / n).sqrt() as f32];
+    (mean, std)
+}
+
+#[inline]
+fn bin_of(v: f32, ch: usize) -> usize {
+    let (lo, hi) = LAB_RANGE[ch];
+    (((v - lo) / (hi - lo)) * (BINS - 1) as f32).round().clamp(0.0, (BINS - 1) as f32) as usize
+}
+
+#[inline]
+fn value_of(bin: usize, ch: usize) -> f32 {
+    let (lo, hi) = LAB_RANGE[ch];
+    lo + (hi - lo) * bin as f32 / (BINS - 1) as f32
+}
+
+fn cdf(values: &[[f32; 3]], ch: usize) -> Vec<f32> {
+    let mut hist = vec![0u32; BINS];
+    for v in values { hist[bin_of(v[ch], ch)] += 1; }
+    let total = values.len().max(1) as f32;
+    let mut acc = 0u32;
+    hist.iter().map(|&n| { acc += n; acc as f32 / total }).collect()
+}
+
+// Source bin -> reference value at the same quantile
+fn matching_table(src: &[[f32; 3]], reference: &[[f32; 3]], ch: usize) -> Vec<f32> {
+    let src_cdf = cdf(src, ch);
+    let ref_cdf = cdf(reference, ch);
+    let mut j = 0usize;
+    src_cdf
+        .iter()
+        .map(|&q| {
+            while j < BINS - 1 && ref_cdf[j] < q { j += 1; }
+            value_of(j, ch)
+        })
+        .collect()
+}
+
+/// Shifts the colors of `img` toward those of `reference`, blended by `strength` (0..1).
+/// Fully transparent pixels are ignored in the statistics and left untouched.
+pub fn apply_color_transfer(img: &mut Rgba32FImage, reference: &Rgba32FImage, mode: ColorTransfer, strength: f32) {
+    let s = strength.clamp(0.0, 1.0);
+    if s <= 0.0 { return; }
+    let src_lab = opaque_lab(img);
+    let ref_lab = opaque_lab(reference);
+    if src_lab.is_empty() || ref_lab.is_empty() { return; }
+
+    let transform: Box<dyn Fn([f32; 3]) -> [f32; 3]> = match mode {
+        ColorTransfer::Reinhard => {
+            let (sm, ss) = mean_std(&src_lab);
+            let (rm, rs) = mean_std(&ref_lab);
+            Box::new(move |lab: [f32; 3]| {
+                let mut out = [0.0f32; 3];
+                for ch in 0..3 {
+                    let scale = if ss[ch] > 1e-3 { rs[ch] / ss[ch] } else { 1.0 };
+                    out[ch] = (lab[ch] - sm[ch]) * scale + rm[ch];
+                }
+                out
+            })
+        }
+        ColorTransfer::Histogram => {
+            let tables: Vec<Vec<f32>> = (0..3).map(|ch| matching_table(&src_lab, &ref_lab, ch)).collect();
+            Box::new(move |lab: [f32; 3]| {
+                [tables[0][bin_of(lab[0], 0)], tables[1][bin_of(lab[1], 1)], tables[2][bin_of(lab[2], 2)]]
+            })
+        }
+    };
+
+    for p in img.pixels_mut() {
+        if p.0[3] <= 0.0 { continue; }
+        let lab = srgb01_to_lab([p.0[0], p.0[1], p.0[2]]);
+        let t = transform(lab);
+        let blended = [
+            lab[0] + (t[0] - lab[0]) * s,
+            lab[1] + (t[1] - lab[1]) * s,
+            lab[2] + (t[2] - lab[2]) * s,
+        ];
+        let [r, g, b] = lab_to_srgb01(blended);
+        p.0[0] = r; p.0[1] = g; p.0[2] = b;
+    }
+}
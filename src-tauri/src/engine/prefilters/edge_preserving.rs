@@ -0,0 +1,240 @@
+use rayon::prelude::*;
+
+use crate::engine::algorithms::RgbaImage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgePreservingFilter {
+    Bilateral,
+    Kuwahara,
+    Guided,
+}
+
+impl EdgePreservingFilter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "bilateral" => Some(Self::Bilateral),
+            "kuwahara" | "anisotropic kuwahara" => Some(Self::Kuwahara),
+            "guided" | "guided filter" => Some(Self::Guided),
+            _ => None,
+        }
+    }
+}
+
+/// Applies an edge-preserving smoothing filter in place. `radius` is the neighborhood in
+/// pixels; `strength` in 0..1 controls how strong an edge must be to survive smoothing.
+pub fn apply_edge_preserving(img: &mut RgbaImage, filter: EdgePreservingFilter, radius: u32, strength: f32) {
+    if img.width() == 0 || img.height() == 0 { return; }
+    let r = radius.clamp(1, 16) as i32;
+    let s = strength.clamp(0.0, 1.0);
+    let out = match filter {
+        EdgePreservingFilter::Bilateral => bilateral(img, r, 8.0 + s * 72.0),
+        EdgePreservingFilter::Kuwahara => anisotropic_kuwahara(img, r, 4.0 + (1.0 - s) * 12.0),
+        EdgePreservingFilter::Guided => guided(img, r, (0.02 + s * 0.2).powi(2)),
+    };
+    for (p, c) in img.pixels_mut().zip(out) {
+        p.0[0] = c[0].round().clamp(0.0, 255.0) as u8;
+        p.0[1] = c[1].round().clamp(0.0, 255.0) as u8;
+        p.0[2] = c[2].round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+fn to_rgb_f32(img: &RgbaImage) -> Vec<[f32; 3]> {
+    img.pixels().map(|p| [p.0[0] as f32, p.0[1] as f32, p.0[2] as f32]).collect()
+}
+
+// Gaussian spatial weight times Gaussian range weight on RGB distance
+fn bilateral(img: &RgbaImage, r: i32, sigma_range: f32) -> Vec<[f32; 3]> {
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    let src = to_rgb_f32(img);
+    let sigma_space = (r as f32 * 0.5).max(0.5);
+    let inv_s = 1.0 / (2.0 * sigma_space * sigma_space);
+    let inv_r = 1.0 / (2.0 * sigma_range * sigma_range);
+    let mut out = vec![[0.0f32; 3]; src.len()];
+    out.par_chunks_mut(w as usize).enumerate().for_each(|(y, row)| {
+        let y = y as i32;
+        for (x, dst) in row.iter_mut().enumerate() {
+            let x = x as i32;
+            let c = src[(y * w + x) as usize];
+            let mut acc = [0.0f32; 3];
+            let mut wsum = 0.0f32;
+            for dy in -r..=r {
+                let ny = (y + dy).clamp(0, h - 1);
+                for dx in -r..=r {
+                    let nx = (x + dx).clamp(0, w - 1);
+                    let q = src[(ny * w + nx) as usize];
+                    let dc = (q[0] - c[0]).powi(2) + (q[1] - c[1]).powi(2) + (q[2] - c[2]).powi(2);
+                    let wt = (-((dx * dx + dy * dy) as f32) * inv_s - dc * inv_r).exp();
+                    acc[0] += q[0] * wt; acc[1] += q[1] * wt; acc[2] += q[2] * wt;
+                    wsum += wt;
+                }
+            }
+            *dst = [acc[0] / wsum, acc[1] / wsum, acc[2] / wsum];
+        }
+    });
+    out
+}
+
+// Mean over a (2r+1)^2 box with clamped borders, via a summed-area table
+fn box_mean(src: &[f32], w: usize, h: usize, r: i32) -> Vec<f32> {
+    let mut sat = vec![0.0f64; (w + 1) * (h + 1)];
+    for y in 0..h {
+        let mut row = 0.0f64;
+        for x in 0..w {
+            row += src[y * w + x] as f64;
+            sat[(y + 1) * (w + 1) + x + 1] = sat[y * (w + 1) + x + 1] + row;
+        }
+    }
+    let mut out = vec![0.0f32; w * h];
+    for y in 0..h as i32 {
+        let y0 = (y - r).max(0) as usize;
+        let y1 = (y + r + 1).min(h as i32) as usize;
+        for x in 0..w as i32 {
+            let x0 = (x - r).max(0) as usize;
+            let x1 = (x + r + 1).min(w as i32) as usize;
+            let sum = sat[y1 * (w + 1) + x1] - sat[y0 * (w + 1) + x1] - sat[y1 * (w + 1) + x0] + sat[y0 * (w + 1) + x0];
+            out[y as usize * w + x as usize] = (sum / ((x1 - x0) * (y1 - y0)) as f64) as f32;
+        }
+    }
+    out
+}
+
+// Self-guided filter (He et al.) per channel on normalized values; eps sets the edge cutoff
+fn guided(img: &RgbaImage, r: i32, eps: f32) -> Vec<[f32; 3]> {
+    let (w, h) = (img.width() as usize, img.height() as usize);
+    let channels: Vec<Vec<f32>> = (0..3)
+        .map(|ch| {
+            let i: Vec<f32> = img.pixels().map(|p| p.0[ch] as f32 / 255.0).collect();
+            let ii: Vec<f32> = i.iter().map(|v| v * v).collect();
+            let mean_i = box_mean(&i, w, h, r);
+            let mean_ii = box_mean(&ii, w, h, r);
+            let mut a = vec![0.0f32; w * h];
+            let mut b = vec![0.0f32; w * h];
+            for k in 0..w * h {
+                let var = (mean_ii[k] - mean_i[k] * mean_i[k]).max(0.0);
+                a[k] = var / (var + eps);
+                b[k] = mean_i[k] - a[k] * mean_i[k];
+            }
+            let mean_a = box_mean(&a, w, h, r);
+            let mean_b = box_mean(&b, w, h, r);
+            (0..w * h).map(|k| (mean_a[k] * i[k] + mean_b[k]) * 255.0).collect()
+        })
+        .collect();
+    (0..w * h).map(|k| [channels[0][k], channels[1][k], channels[2][k]]).collect()
+}
+
+fn luma(c: [f32; 3]) -> f32 {
+    0.299 * c[0] + 0.587 * c[1] + 0.114 * c[2]
+}
+
+// Local orientation (radians) and anisotropy in 0..1 from a smoothed structure tensor
+fn orientation_field(src: &[[f32; 3]], w: usize, h: usize) -> Vec<(f32, f32)> {
+    let lum = |x: i32, y: i32| -> f32 {
+        let x = x.clamp(0, w as i32 - 1) as usize;
+        let y = y.clamp(0, h as i32 - 1) as usize;
+        luma(src[y * w + x]) / 255.0
+    };
+    let mut exx = vec![0.0f32; w * h];
+    let mut eyy = vec![0.0f32; w * h];
+    let mut exy = vec![0.0f32; w * h];
+    for y in 0..h as i32 {
+        for x in 0..w as i32 {
+            let gx = (lum(x + 1, y - 1) + 2.0 * lum(x + 1, y) + lum(x + 1, y + 1)
+                - lum(x - 1, y - 1) - 2.0 * lum(x - 1, y) - lum(x - 1, y + 1)) / 4.0;
+            let gy = (lum(x - 1, y + 1) + 2.0 * lum(x, y + 1) + lum(x + 1, y + 1)
+                - lum(x - 1, y - 1) - 2.0 * lum(x, y - 1) - lum(x + 1, y - 1)) / 4.0;
+            let k = y as usize * w + x as usize;
+            exx[k] = gx * gx;
+            eyy[k] = gy * gy;
+            exy[k] = gx * gy;
+        }
+    }
+    let exx = box_mean(&exx, w, h, 2);
+    let eyy = box_mean(&eyy, w, h, 2);
+    let exy = box_mean(&exy, w, h, 2);
+    (0..w * h)
+        .map(|k| {
+            let (e, g, f) = (exx[k], eyy[k], exy[k]);
+            let root = ((e - g).powi(2) + 4.0 * f * f).sqrt();
+            let l1 = 0.5 * (e + g + root);
+            let l2 = 0.5 * (e + g - root);
+            // Eigenvector of the minor eigenvalue points along the edge
+            let (tx, ty) = (l1 - e, -f);
+            let phi = if tx.abs() + ty.abs() > 1e-12 { ty.atan2(tx) } else { 0.0 };
+            let aniso = if l1 + l2 > 1e-12 { (l1 - l2) / (l1 + l2) } else { 0.0 };
+            (phi, aniso)
+        })
+        .collect()
+}
+
+// Anisotropic Kuwahara (Kyprianidis et al.): 8 sectors of an ellipse aligned to the local
+// edge; sectors with low variance dominate, so regions flatten while edges stay sharp
+fn anisotropic_kuwahara(img: &RgbaImage, r: i32, q: f32) -> Vec<[f32; 3]> {
+    const SECTORS: usize = 8;
+    const ALPHA: f32 = 1.0;
+    let (w, h) = (img.width() as usize, img.height() as usize);
+    let src = to_rgb_f32(img);
+    let field = orientation_field(&src, w, h);
+    let mut out = vec![[0.0f32; 3]; w * h];
+    out.par_chunks_mut(w).enumerate().for_each(|(y, row)| {
+        for (x, dst) in row.iter_mut().enumerate() {
+            let (phi, aniso) = field[y * w + x];
+            let a = r as f32 * (ALPHA + aniso) / ALPHA;
+            let b = r as f32 * ALPHA / (ALPHA + aniso);
+            let (cos_p, sin_p) = (phi.cos(), phi.sin());
+            let reach = a.ceil() as i32;
+            let mut sum = [[0.0f32; 3]; SECTORS];
+            let mut sq = [[0.0f32; 3]; SECTORS];
+            let mut wsum = [0.0f32; SECTORS];
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    // Rotate into the edge frame, then squash into the unit disc
+                    let u = (dx as f32 * cos_p + dy as f32 * sin_p) / a;
+                    let v = (-(dx as f32) * sin_p + dy as f32 * cos_p) / b;
+                    let d2 = u * u + v * v;
+                    if d2 > 1.0 { continue; }
+                    let nx = (x as i32 + dx).clamp(0, w as i32 - 1) as usize;
+                    let ny = (y as i32 + dy).clamp(0, h as i32 - 1) as usize;
+                    let c = src[ny * w + nx];
+                    let gauss = (-2.0 * d2).exp();
+                    let sector = if d2 == 0.0 {
+                        None
+                    } else {
+                        let ang = v.atan2(u).rem_euclid(std::f32::consts::TAU);
+                        Some(((ang / std::f32::consts::TAU) * SECTORS as f32) as usize % SECTORS)
+                    };
+                    // The center pixel belongs to every sector
+                    let targets = match sector { Some(k) => k..k + 1, None => 0..SECTORS };
+                    for k in targets {
+                        for ch in 0..3 {
+                            sum[k][ch] += c[ch] * gauss;
+                            sq[k][ch] += c[ch] * c[ch] * gauss;
+                        }
+                        wsum[k] += gauss;
+                    }
+                }
+            }
+            let mut acc = [0.0f64; 3];
+            let mut total = 0.0f64;
+            for k in 0..SECTORS {
+                if wsum[k] <= 0.0 { continue; }
+                let mut mean = [0.0f32; 3];
+                let mut var = 0.0f32;
+                for ch in 0..3 {
+                    mean[ch] = sum[k][ch] / wsum[k];
+                    var += (sq[k][ch] / wsum[k] - mean[ch] * mean[ch]).max(0.0);
+                }
+                // Pivot at a deviation of 8 levels; noisier sectors fade out quickly
+                let sd = (var.sqrt() / 8.0) as f64;
+                let wk = 1.0 / (1.0 + sd.powf(q as f64));
+                for ch in 0..3 { acc[ch] += mean[ch] as f64 * wk; }
+                total += wk;
+            }
+            *dst = if total > 0.0 {
+                [(acc[0] / total) as f32, (acc[1] / total) as f32, (acc[2] / total) as f32]
+            } else {
+                src[y * w + x]
+            };
+        }
+    });
+    out
+}
@@ -0,0 +1,149 @@
+use image::Rgba32FImage;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Equalize {
+    /// Global histogram equalization
+    Global,
+    /// Contrast-limited adaptive equalization over a `tiles` x `tiles` grid
+    Clahe { tiles: u32, clip_limit: f32 },
+}
+
+impl Equalize {
+    pub fn from_request(mode: Option<&str>, tiles: Option<u32>, clip_limit: Option<f32>) -> Option<Self> {
+        match mode.map(|m| m.trim().to_ascii_lowercase()).as_deref() {
+            Some("global") | Some("histogram") => Some(Self::Global),
+            Some("clahe") | Some("adaptive") => Some(Self::Clahe {
+                tiles: tiles.unwrap_or(8).clamp(1, 64),
+                clip_limit: clip_limit.unwrap_or(2.0).max(1.0),
+            }),
+            _ => None,
+        }
+    }
+}
+
+// Full-range BT.601 YCbCr on a 0..255 scale; equalizing Y alone keeps hue and saturation
+#[inline]
+fn to_ycbcr(p: [f32; 4]) -> (f32, f32, f32) {
+    let (r, g, b) = (p[0] * 255.0, p[1] * 255.0, p[2] * 255.0);
+    let y = 0.299 * r + 0.587 * g + 0.114 * b;
+    (y, -0.168736 * r - 0.331264 * g + 0.5 * b, 0.5 * r - 0.418688 * g - 0.081312 * b)
+}
+
+#[inline]
+fn from_ycbcr(y: f32, cb: f32, cr: f32) -> [f32; 3] {
+    [
+        ((y + 1.402 * cr) / 255.0).clamp(0.0, 1.0),
+        ((y - 0.344136 * cb - 0.714136 * cr) / 255.0).clamp(0.0, 1.0),
+        ((y + 1.772 * cb) / 255.0).clamp(0.0, 1.0),
+    ]
+}
+
+// Looks up a CDF mapping at fractional luma so high-precision input keeps smooth gradients
+#[inline]
+fn map_at(map: &[f32; 256], y: f32) -> f32 {
+    let y = y.clamp(0.0, 255.0);
+    let i0 = (y.floor() as usize).min(254);
+    let f = y - i0 as f32;
+    map[i0] * (1.0 - f) + map[i0 + 1] * f
+}
+
+fn cdf_mapping(hist: &[u32; 256]) -> [f32; 256] {
+    let total: u32 = hist.iter().sum();
+    let mut map = [0.0f32; 256];
+    if total == 0 {
+        for (i, m) in map.iter_mut().enumerate() { *m = i as f32; }
+        return map;
+    }
+    // Offset by the first occupied bin so the darkest level maps to 0
+    let first = hist.iter().copied().find(|&n| n > 0).unwrap_or(0);
+    let denom = (total - first).max(1) as f32;
+    let mut acc = 0u32;
+    for (m, &n) in map.iter_mut().zip(hist.iter()) {
+        acc += n;
+        *m = (acc.saturating_sub(first) as f32 / denom) * 255.0;
+    }
+    map
+}
+
+// Clip bins at the limit and spread the excess evenly over all bins
+fn clip_histogram(hist: &mut [u32; 256], limit: u32) {
+    let mut excess = 0u32;
+    for n in hist.iter_mut() {
+        if *n > limit {
+            excess += *n - limit;
+            *n = limit;
+        }
+    }
+    let share = excess / 256;
+    let rest = (excess % 256) as usize;
+    for (i, n) in hist.iter_mut().enumerate() {
+        *n += share + u32::from(i < rest);
+    }
+}
+
+pub fn apply_equalize(img: &mut Rgba32FImage, mode: Equalize) {
+    let (w, h) = (img.width(), img.height());
+    if w == 0 || h == 0 { return; }
+    let ycc: Vec<(f32, f32, f32)> = img.pixels().map(|p| to_ycbcr(p.0)).collect();
+    let luma_bin = |y: f32| y.round().clamp(0.0, 255.0) as usize;
+    match mode {
+        Equalize::Global => {
+            let mut hist = [0u32; 256];
+            for (p, &(y, _, _)) in img.pixels().zip(ycc.iter()) {
+                if p.0[3] > 0.0 { hist[luma_bin(y)] += 1; }
+            }
+            let map = cdf_mapping(&hist);
+            for (p, &(y, cb, cr)) in img.pixels_mut().zip(ycc.iter()) {
+                let [r, g, b] = from_ycbcr(map_at(&map, y), cb, cr);
+                p.0[0] = r; p.0[1] = g; p.0[2] = b;
+            }
+        }
+        Equalize::Clahe { tiles, clip_limit } => {
+            let tx = tiles.min(w);
+            let ty = tiles.min(h);
+            // Tile bounds are proportional so every tile gets pixels, even when the tile
+            // count doesn't divide the image
+            let span = |k: u32, n: u32, len: u32| (k * len / n)..((k + 1) * len / n);
+            let mut maps = vec![[0.0f32; 256]; (tx * ty) as usize];
+            for j in 0..ty {
+                for i in 0..tx {
+                    let mut hist = [0u32; 256];
+                    let mut count = 0u32;
+                    for y in span(j, ty, h) {
+                        for x in span(i, tx, w) {
+                            let k = (y * w + x) as usize;
+                            if img.get_pixel(x, y).0[3] <= 0.0 { continue; }
+                            hist[luma_bin(ycc[k].0)] += 1;
+                            count += 1;
+                        }
+                    }
+                    let limit = ((clip_limit * count as f32 / 256.0).ceil() as u32).max(1);
+                    clip_histogram(&mut hist, limit);
+                    maps[(j * tx + i) as usize] = cdf_mapping(&hist);
+                }
+            }
+            // Bilinear blend of the four nearest tile mappings, anchored at tile centers
+            let (tile_w, tile_h) = (w as f32 / tx as f32, h as f32 / ty as f32);
+            let (tx, ty) = (tx as i32, ty as i32);
+            for y in 0..h {
+                let fy = (y as f32 + 0.5) / tile_h - 0.5;
+                let j0 = (fy.floor() as i32).clamp(0, ty - 1);
+                let j1 = (j0 + 1).min(ty - 1);
+                let wy = (fy - j0 as f32).clamp(0.0, 1.0);
+                for x in 0..w {
+                    let fx = (x as f32 + 0.5) / tile_w - 0.5;
+                    let i0 = (fx.floor() as i32).clamp(0, tx - 1);
+                    let i1 = (i0 + 1).min(tx - 1);
+                    let wx = (fx - i0 as f32).clamp(0.0, 1.0);
+                    let (yv, cb, cr) = ycc[(y * w + x) as usize];
+                    let m = |i: i32, j: i32| map_at(&maps[(j * tx + i) as usize], yv);
+                    let top = m(i0, j0) * (1.0 - wx) + m(i1, j0) * wx;
+                    let bottom = m(i0, j1) * (1.0 - wx) + m(i1, j1) * wx;
+                    let [r, g, b] = from_ycbcr(top * (1.0 - wy) + bottom * wy, cb, cr);
+                    let p = img.get_pixel_mut(x, y);
+                    p.0[0] = r; p.0[1] = g; p.0[2] = b;
+                }
+            }
+        }
+    }
+}
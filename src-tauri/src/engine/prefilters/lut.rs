@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use thiserror::Error;
+
+use image::Rgba32FImage;
+
+#[derive(Debug, Error)]
+pub enum LutError {
+    #[error("invalid .cube file: {0}")]
+    Parse(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+// Parsed LUTs by path, with the file's modification time when they were read
+type LutCache = Mutex<HashMap<PathBuf, (Option<SystemTime>, Arc<CubeLut>)>>;
+static LUT_CACHE: OnceLock<LutCache> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LutInterpolation {
+    Trilinear,
+    Tetrahedral,
+}
+
+impl LutInterpolation {
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name.map(|n| n.trim().to_ascii_lowercase()).as_deref() {
+            Some("trilinear") => Self::Trilinear,
+            _ => Self::Tetrahedral,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum LutTable {
+    /// Per-channel curves, `size` entries each
+    OneD(Vec<[f32; 3]>),
+    /// `size`^3 entries, red varying fastest
+    ThreeD(Vec<[f32; 3]>),
+}
+
+/// Adobe/Resolve `.cube` LUT (1D or 3D).
+#[derive(Debug, Clone)]
+pub struct CubeLut {
+    pub title: Option<String>,
+    size: usize,
+    domain_min: [f32; 3],
+    domain_max: [f32; 3],
+    table: LutTable,
+}
+
+fn parse_floats<const N: usize>(parts: &[&str], line: &str) -> Result<[f32; N], LutError> {
+    if parts.len() < N {
+        return Err(LutError::Parse(format!("expected {} values: {}", N, line)));
+    }
+    let mut out = [0.0f32; N];
+    for (o, p) in out.iter_mut().zip(parts) {
+        *o = p.parse::<f32>().map_err(|_| LutError::Parse(format!("bad number: {}", line)))?;
+    }
+    Ok(out)
+}
+
+impl CubeLut {
+    pub fn parse(contents: &str) -> Result<Self, LutError> {
+        let mut title = None;
+        let mut size_1d: Option<usize> = None;
+        let mut size_3d: Option<usize> = None;
+        let mut domain_min = [0.0f32; 3];
+        let mut domain_max = [1.0f32; 3];
+        let mut data: Vec<[f32; 3]> = Vec::new();
+        for raw in contents.lines() {
+            let line = raw.trim_start_matches('\u{FEFF}').trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts[0] {
+                "TITLE" => {
+                    title = Some(line[5..].trim().trim_matches('"').to_string());
+                }
+                "LUT_1D_SIZE" | "LUT_3D_SIZE" => {
+                    let n = parts
+                        .get(1)
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .filter(|n| *n >= 2)
+                        .ok_or_else(|| LutError::Parse(format!("bad size: {}", line)))?;
+                    if parts[0] == "LUT_1D_SIZE" { size_1d = Some(n) } else { size_3d = Some(n) }
+                }
+                "DOMAIN_MIN" => domain_min = parse_floats::<3>(&parts[1..], line)?,
+                "DOMAIN_MAX" => domain_max = parse_floats::<3>(&parts[1..], line)?,
+                // Resolve-style single input range for all channels
+                "LUT_1D_INPUT_RANGE" | "LUT_3D_INPUT_RANGE" => {
+                    let [lo, hi] = parse_floats::<2>(&parts[1..], line)?;
+                    domain_min = [lo; 3];
+                    domain_max = [hi; 3];
+                }
+                kw if kw.starts_with(|c: char| c.is_ascii_alphabetic()) => {}
+                _ => data.push(parse_floats::<3>(&parts, line)?),
+            }
+        }
+        if domain_min.iter().zip(domain_max.iter()).any(|(lo, hi)| hi <= lo) {
+            return Err(LutError::Parse("DOMAIN_MAX must exceed DOMAIN_MIN".into()));
+        }
+        let (size, table) = match (size_3d, size_1d) {
+            (Some(n), _) => {
+                if data.len() != n * n * n {
+                    return Err(LutError::Parse(format!("expected {} entries, found {}", n * n * n, data.len())));
+                }
+                (n, LutTable::ThreeD(data))
+            }
+            (None, Some(n)) => {
+                if data.len() != n {
+                    return Err(LutError::Parse(format!("expected {} entries, found {}", n, data.len())));
+                }
+                (n, LutTable::OneD(data))
+            }
+            (None, None) => return Err(LutError::Parse("missing LUT_1D_SIZE or LUT_3D_SIZE".into())),
+        };
+        Ok(Self { title, size, domain_min, domain_max, table })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LutError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Like [`CubeLut::load`], but reuses the parsed LUT until the file's modification
+    /// time changes, so repeated previews don't re-read and re-parse it.
+    pub fn load_cached(path: &Path) -> Result<Arc<Self>, LutError> {
+        let modified = std::fs::metadata(path)?.modified().ok();
+        let cache = LUT_CACHE.get_or_init(Default::default);
+        let cached = cache.lock().unwrap_or_else(|e| e.into_inner()).get(path).cloned();
+        if let Some((stamp, lut)) = cached {
+            if stamp.is_some() && stamp == modified {
+                return Ok(lut);
+            }
+        }
+        let lut = Arc::new(Self::load(path)?);
+        cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.to_path_buf(), (modified, Arc::clone(&lut)));
+        Ok(lut)
+    }
+
+    // Input color to fractional table coordinates in 0..size-1
+    #[inline]
+    fn coords(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let n1 = (self.size - 1) as f32;
+        let mut c = [0.0f32; 3];
+        for (i, v) in c.iter_mut().enumerate() {
+            let t = (rgb[i] - self.domain_min[i]) / (self.domain_max[i] - self.domain_min[i]);
+            *v = t.clamp(0.0, 1.0) * n1;
+        }
+        c
+    }
+
+    /// Maps a normalized (0..1) RGB color through the LUT.
+    pub fn sample(&self, rgb: [f32; 3], interp: LutInterpolation) -> [f32; 3] {
+        let c = self.coords(rgb);
+        match &self.table {
+            LutTable::OneD(t) => {
+                let mut out = [0.0f32; 3];
+                for (ch, o) in out.iter_mut().enumerate() {
+                    let i0 = (c[ch].floor() as usize).min(self.size - 2);
+                    let f = c[ch] - i0 as f32;
+                    *o = t[i0][ch] * (1.0 - f) + t[i0 + 1][ch] * f;
+                }
+                out
+            }
+            LutTable::ThreeD(t) => {
+                let n = self.size;
+                let base = [
+                    (c[0].floor() as usize).min(n - 2),
+                    (c[1].floor() as usize).min(n - 2),
+                    (c[2].floor() as usize).min(n - 2),
+                ];
+                let f = [c[0] - base[0] as f32, c[1] - base[1] as f32, c[2] - base[2] as f32];
+                let at = |dr: usize, dg: usize, db: usize| -> [f32; 3] {
+                    t[(base[2] + db) * n * n + (base[1] + dg) * n + base[0] + dr]
+                };
+                match interp {
+                    LutInterpolation::Trilinear => trilinear(&at, f),
+                    LutInterpolation::Tetrahedral => tetrahedral(&at, f),
+                }
+            }
+        }
+    }
+
+    /// Grades the image through the LUT, blending with the original by `strength` (0..1).
+    pub fn apply(&self, img: &mut Rgba32FImage, strength: f32, interp: LutInterpolation) {
+        let s = strength.clamp(0.0, 1.0);
+        if s <= 0.0 { return; }
+        for p in img.pixels_mut() {
+            let src = [p.0[0], p.0[1], p.0[2]];
+            let graded = self.sample(src, interp);
+            for ch in 0..3 {
+                p.0[ch] = (src[ch] + (graded[ch] - src[ch]) * s).clamp(0.0, 1.0);
+            }
+        }
+    }
+}
+
+fn lerp3(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t]
+}
+
+fn trilinear(at: &impl Fn(usize, usize, usize) -> [f32; 3], f: [f32; 3]) -> [f32; 3] {
+    let c00 = lerp3(at(0, 0, 0), at(1, 0, 0), f[0]);
+    let c10 = lerp3(at(0, 1, 0), at(1, 1, 0), f[0]);
+    let c01 = lerp3(at(0, 0, 1), at(1, 0, 1), f[0]);
+    let c11 = lerp3(at(0, 1, 1), at(1, 1, 1), f[0]);
+    lerp3(lerp3(c00, c10, f[1]), lerp3(c01, c11, f[1]), f[2])
+}
+
+// Splits the cube into six tetrahedra along the main diagonal; smoother on neutral axes
+fn tetrahedral(at: &impl Fn(usize, usize, usize) -> [f32; 3], f: [f32; 3]) -> [f32; 3] {
+    let [fr, fg, fb] = f;
+    let c000 = at(0, 0, 0);
+    let c111 = at(1, 1, 1);
+    // (weights, first vertex, second vertex) for the tetrahedron containing f
+    let (w, v1, v2) = if fr > fg {
+        if fg > fb {
+            ([1.0 - fr, fr - fg, fg - fb, fb], at(1, 0, 0), at(1, 1, 0))
+        } else if fr > fb {
+            ([1.0 - fr, fr - fb, fb - fg, fg], at(1, 0, 0), at(1, 0, 1))
+        } else {
+            ([1.0 - fb, fb - fr, fr - fg, fg], at(0, 0, 1), at(1, 0, 1))
+        }
+    } else if fb > fg {
+        ([1.0 - fb, fb - fg, fg - fr, fr], at(0, 0, 1), at(0, 1, 1))
+    } else if fb > fr {
+        ([1.0 - fg, fg - fb, fb - fr, fr], at(0, 1, 0), at(0, 1, 1))
+    } else {
+        ([1.0 - fg, fg - fr, fr - fb, fb], at(0, 1, 0), at(1, 1, 0))
+    };
+    let mut out = [0.0f32; 3];
+    for (ch, o) in out.iter_mut().enumerate() {
+        *o = w[0] * c000[ch] + w[1] * v1[ch] + w[2] * v2[ch] + w[3] * c111[ch];
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2x2x2 identity cube, red varying fastest
+    const IDENTITY_3D: &str = "TITLE \"Identity\"\n# comment\nLUT_3D_SIZE 2\n\
+        0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        assert!(a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-5), "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn parses_3d_header_and_table_order() {
+        let lut = CubeLut::parse(IDENTITY_3D).unwrap();
+        assert_eq!(lut.title.as_deref(), Some("Identity"));
+        assert_eq!(lut.size, 2);
+        let LutTable::ThreeD(t) = &lut.table else { panic!("expected a 3D table") };
+        assert_eq!(t[1], [1.0, 0.0, 0.0]);
+        assert_eq!(t[2], [0.0, 1.0, 0.0]);
+        assert_eq!(t[4], [0.0, 0.0, 1.0]);
+        for interp in [LutInterpolation::Trilinear, LutInterpolation::Tetrahedral] {
+            assert_close(lut.sample([0.25, 0.5, 0.75], interp), [0.25, 0.5, 0.75]);
+        }
+    }
+
+    #[test]
+    fn parses_1d_with_input_range() {
+        let lut = CubeLut::parse("LUT_1D_SIZE 3\nLUT_1D_INPUT_RANGE 0 2\n0 0 0\n0.5 0.5 0.5\n1 1 1\n").unwrap();
+        assert_eq!(lut.domain_max, [2.0; 3]);
+        assert!(matches!(lut.table, LutTable::OneD(_)));
+        assert_close(lut.sample([1.0, 0.5, 2.0], LutInterpolation::Tetrahedral), [0.5, 0.25, 1.0]);
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        assert!(CubeLut::parse("0 0 0\n1 1 1\n").is_err());
+        assert!(CubeLut::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(CubeLut::parse("LUT_1D_SIZE 2\n0 0 0\n1 x 1\n").is_err());
+        assert!(CubeLut::parse("LUT_1D_SIZE 2\nDOMAIN_MIN 1 1 1\nDOMAIN_MAX 0 0 0\n0 0 0\n1 1 1\n").is_err());
+    }
+}
@@ -0,0 +1,7 @@
+pub mod edge_preserving;
+pub mod sharpen;
+pub mod tone;
+pub mod equalize;
+pub mod lut;
+pub mod color_transfer;
+pub mod background;
@@ -0,0 +1,62 @@
+use image::Rgba32FImage;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharpenStage {
+    /// Full-resolution source, before the grid downscale
+    Source,
+    /// Logical grid, right after the downscale
+    Grid,
+    Both,
+}
+
+impl SharpenStage {
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name.map(|n| n.trim().to_ascii_lowercase()).as_deref() {
+            Some("grid") => Self::Grid,
+            Some("both") => Self::Both,
+            _ => Self::Source,
+        }
+    }
+
+    pub fn includes_source(self) -> bool { matches!(self, Self::Source | Self::Both) }
+    pub fn includes_grid(self) -> bool { matches!(self, Self::Grid | Self::Both) }
+}
+
+/// Unsharp mask: adds `amount` x (original - gaussian(radius)) wherever the difference
+/// exceeds `threshold` levels, so flat noisy areas are left alone.
+pub fn unsharp_mask(img: &mut Rgba32FImage, radius: f32, amount: f32, threshold: u8) {
+    if amount <= 0.0 || radius <= 0.0 { return; }
+    let blurred = image::imageops::blur(img, radius);
+    let threshold = threshold as f32 / 255.0;
+    for (p, b) in img.pixels_mut().zip(blurred.pixels()) {
+        for ch in 0..3 {
+            let diff = p.0[ch] - b.0[ch];
+            if diff.abs() < threshold { continue; }
+            p.0[ch] = (p.0[ch] + diff * amount).clamp(0.0, 1.0);
+        }
+    }
+}
+
+/// Laplacian edge boost: subtracts `strength` x the 8-neighbour Laplacian, which darkens
+/// the dark side and brightens the light side of every edge.
+pub fn laplacian_boost(img: &mut Rgba32FImage, strength: f32) {
+    if strength <= 0.0 { return; }
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    let src = img.clone();
+    let at = |x: i32, y: i32, ch: usize| -> f32 {
+        src.get_pixel(x.clamp(0, w - 1) as u32, y.clamp(0, h - 1) as u32).0[ch]
+    };
+    for y in 0..h {
+        for x in 0..w {
+            let p = img.get_pixel_mut(x as u32, y as u32);
+            for ch in 0..3 {
+                let mut neighbours = 0.0;
+                for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                    neighbours += at(x + dx, y + dy, ch);
+                }
+                let lap = neighbours - 8.0 * at(x, y, ch);
+                p.0[ch] = (at(x, y, ch) - strength * lap / 8.0).clamp(0.0, 1.0);
+            }
+        }
+    }
+}
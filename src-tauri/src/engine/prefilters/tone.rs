@@ -0,0 +1,169 @@
+use image::Rgba32FImage;
+
+use crate::engine::algorithms::RgbaImage;
+use crate::types::{ChannelLevels, Levels, ToneCurves};
+
+// Fraction of pixels clipped at each end of the histogram by auto levels
+const AUTO_LEVELS_CLIP: f32 = 0.005;
+
+/// Per-channel 8-bit lookup tables for the tone stage.
+#[derive(Debug, Clone)]
+pub struct ToneLut {
+    pub channels: [[u8; 256]; 3],
+}
+
+impl ToneLut {
+    pub fn is_identity(&self) -> bool {
+        self.channels.iter().all(|c| c.iter().enumerate().all(|(i, v)| *v as usize == i))
+    }
+
+    pub fn apply(&self, img: &mut RgbaImage) {
+        for p in img.pixels_mut() {
+            p.0[0] = self.channels[0][p.0[0] as usize];
+            p.0[1] = self.channels[1][p.0[1] as usize];
+            p.0[2] = self.channels[2][p.0[2] as usize];
+        }
+    }
+}
+
+// The mapping while it is being composed, kept in float so the chained steps round once
+struct ToneChain {
+    channels: [[f32; 256]; 3],
+}
+
+impl ToneChain {
+    fn identity() -> Self {
+        let mut ramp = [0.0f32; 256];
+        for (i, v) in ramp.iter_mut().enumerate() { *v = i as f32; }
+        Self { channels: [ramp; 3] }
+    }
+
+    // Chains `f` after the current mapping of channel `ch`
+    fn then(&mut self, ch: usize, f: impl Fn(f32) -> f32) {
+        for v in self.channels[ch].iter_mut() {
+            *v = f(*v).clamp(0.0, 255.0);
+        }
+    }
+
+    fn finish(self) -> ToneLut {
+        ToneLut { channels: self.channels.map(|c| c.map(|v| v.round() as u8)) }
+    }
+}
+
+fn levels_fn(l: ChannelLevels) -> impl Fn(f32) -> f32 {
+    let black = l.black as f32;
+    let white = (l.white as f32).max(black + 1.0);
+    let inv = 1.0 / l.midtone.clamp(0.05, 10.0);
+    move |v| ((v - black) / (white - black)).clamp(0.0, 1.0).powf(inv) * 255.0
+}
+
+/// Black/white points per channel that clip a small fraction of the histogram at each end.
+pub fn auto_levels(img: &Rgba32FImage) -> [ChannelLevels; 3] {
+    let mut hist = [[0u32; 256]; 3];
+    let mut total = 0u32;
+    for p in img.pixels() {
+        if p.0[3] <= 0.0 { continue; }
+        for ch in 0..3 { hist[ch][(p.0[ch] * 255.0).round().clamp(0.0, 255.0) as usize] += 1; }
+        total += 1;
+    }
+    let mut out = [ChannelLevels::default(); 3];
+    if total == 0 { return out; }
+    let clip = (total as f32 * AUTO_LEVELS_CLIP) as u32;
+    for (levels, h) in out.iter_mut().zip(hist.iter()) {
+        let mut acc = 0u32;
+        let black = h.iter().position(|&n| { acc += n; acc > clip }).unwrap_or(0);
+        acc = 0;
+        let white = 255 - h.iter().rev().position(|&n| { acc += n; acc > clip }).unwrap_or(0);
+        if white > black {
+            levels.black = black as u8;
+            levels.white = white as u8;
+        }
+    }
+    out
+}
+
+/// Monotone cubic (Fritsch-Carlson) interpolation through the control points, so curves
+/// never overshoot or reverse between points. Flat beyond the first and last point.
+pub fn monotone_spline(points: &[[f32; 2]]) -> Option<impl Fn(f32) -> f32> {
+    let mut pts: Vec<[f32; 2]> = points
+        .iter()
+        .filter(|p| p[0].is_finite() && p[1].is_finite())
+        .map(|p| [p[0].clamp(0.0, 255.0), p[1].clamp(0.0, 255.0)])
+        .collect();
+    pts.sort_by(|a, b| a[0].partial_cmp(&b[0]).unwrap_or(std::cmp::Ordering::Equal));
+    pts.dedup_by(|a, b| (a[0] - b[0]).abs() < 1e-3);
+    if pts.len() < 2 { return None; }
+    let n = pts.len();
+    let delta: Vec<f32> = (0..n - 1)
+        .map(|i| (pts[i + 1][1] - pts[i][1]) / (pts[i + 1][0] - pts[i][0]))
+        .collect();
+    let mut m = vec![0.0f32; n];
+    m[0] = delta[0];
+    m[n - 1] = delta[n - 2];
+    for i in 1..n - 1 {
+        m[i] = if delta[i - 1] * delta[i] <= 0.0 { 0.0 } else { 0.5 * (delta[i - 1] + delta[i]) };
+    }
+    for i in 0..n - 1 {
+        if delta[i] == 0.0 {
+            m[i] = 0.0;
+            m[i + 1] = 0.0;
+            continue;
+        }
+        let a = m[i] / delta[i];
+        let b = m[i + 1] / delta[i];
+        let s = a * a + b * b;
+        if s > 9.0 {
+            let t = 3.0 / s.sqrt();
+            m[i] = t * a * delta[i];
+            m[i + 1] = t * b * delta[i];
+        }
+    }
+    Some(move |x: f32| {
+        if x <= pts[0][0] { return pts[0][1]; }
+        if x >= pts[n - 1][0] { return pts[n - 1][1]; }
+        let i = pts.windows(2).position(|w| x < w[1][0]).unwrap_or(n - 2);
+        let h = pts[i + 1][0] - pts[i][0];
+        let t = (x - pts[i][0]) / h;
+        let (t2, t3) = (t * t, t * t * t);
+        (2.0 * t3 - 3.0 * t2 + 1.0) * pts[i][1]
+            + (t3 - 2.0 * t2 + t) * h * m[i]
+            + (-2.0 * t3 + 3.0 * t2) * pts[i + 1][1]
+            + (t3 - t2) * h * m[i + 1]
+    })
+}
+
+/// Builds the tone LUT in order: auto levels, levels (master then channel), curves
+/// (master then channel), then the legacy global gamma. The steps are composed in float
+/// and rounded once at the end; gamma on its own therefore rounds to nearest, where the
+/// old standalone gamma pass truncated (up to one level darker).
+pub fn build_tone_lut(
+    auto: Option<[ChannelLevels; 3]>,
+    levels: Option<&Levels>,
+    curves: Option<&ToneCurves>,
+    gamma: Option<f32>,
+) -> ToneLut {
+    let mut lut = ToneChain::identity();
+    if let Some(auto) = auto {
+        for (ch, l) in auto.into_iter().enumerate() { lut.then(ch, levels_fn(l)); }
+    }
+    if let Some(levels) = levels {
+        for (ch, per) in [levels.red, levels.green, levels.blue].into_iter().enumerate() {
+            if let Some(master) = levels.master { lut.then(ch, levels_fn(master)); }
+            if let Some(l) = per { lut.then(ch, levels_fn(l)); }
+        }
+    }
+    if let Some(curves) = curves {
+        let master = monotone_spline(&curves.master);
+        for (ch, per) in [&curves.red, &curves.green, &curves.blue].into_iter().enumerate() {
+            if let Some(f) = master.as_ref() { lut.then(ch, f); }
+            if let Some(f) = monotone_spline(per) { lut.then(ch, f); }
+        }
+    }
+    if let Some(g) = gamma {
+        if (g - 1.0).abs() > 0.001 {
+            let inv = 1.0 / g.max(0.05);
+            for ch in 0..3 { lut.then(ch, |v| (v / 255.0).powf(inv) * 255.0); }
+        }
+    }
+    lut.finish()
+}
@@ -0,0 +1,136 @@
+use image::RgbaImage;
+
+use super::px;
+
+/// Scale2x (AdvMAME2x / EPX): copies an edge neighbour into a corner when the two
+/// neighbours meeting there agree and the opposite ones don't. Never introduces new colors.
+pub fn scale2x(img: &RgbaImage) -> RgbaImage {
+    let (w, h) = img.dimensions();
+    let mut out = RgbaImage::new(w * 2, h * 2);
+    for y in 0..h as i32 {
+        for x in 0..w as i32 {
+            let e = px(img, x, y);
+            let b = px(img, x, y - 1);
+            let d = px(img, x - 1, y);
+            let f = px(img, x + 1, y);
+            let hh = px(img, x, y + 1);
+            let (ox, oy) = (x as u32 * 2, y as u32 * 2);
+            if b != hh && d != f {
+                out.put_pixel(ox, oy, if d == b { d } else { e });
+                out.put_pixel(ox + 1, oy, if b == f { f } else { e });
+                out.put_pixel(ox, oy + 1, if d == hh { d } else { e });
+                out.put_pixel(ox + 1, oy + 1, if hh == f { f } else { e });
+            } else {
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    out.put_pixel(ox + dx, oy + dy, e);
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Scale3x (AdvMAME3x).
+pub fn scale3x(img: &RgbaImage) -> RgbaImage {
+    let (w, h) = img.dimensions();
+    let mut out = RgbaImage::new(w * 3, h * 3);
+    for y in 0..h as i32 {
+        for x in 0..w as i32 {
+            let a = px(img, x - 1, y - 1);
+            let b = px(img, x, y - 1);
+            let c = px(img, x + 1, y - 1);
+            let d = px(img, x - 1, y);
+            let e = px(img, x, y);
+            let f = px(img, x + 1, y);
+            let g = px(img, x - 1, y + 1);
+            let hh = px(img, x, y + 1);
+            let i = px(img, x + 1, y + 1);
+            let mut cell = [e; 9];
+            if b != hh && d != f {
+                cell[0] = if d == b { d } else { e };
+                cell[1] = if (d == b && e != c) || (b == f && e != a) { b } else { e };
+                cell[2] = if b == f { f } else { e };
+                cell[3] = if (d == b && e != g) || (d == hh && e != a) { d } else { e };
+                cell[5] = if (b == f && e != i) || (hh == f && e != c) { f } else { e };
+                cell[6] = if d == hh { d } else { e };
+                cell[7] = if (d == hh && e != i) || (hh == f && e != g) { hh } else { e };
+                cell[8] = if hh == f { f } else { e };
+            }
+            let (ox, oy) = (x as u32 * 3, y as u32 * 3);
+            for (k, p) in cell.into_iter().enumerate() {
+                out.put_pixel(ox + k as u32 % 3, oy + k as u32 / 3, p);
+            }
+        }
+    }
+    out
+}
+
+/// Eagle 2x: a corner takes the color of its three outer neighbours when they all agree.
+pub fn eagle2x(img: &RgbaImage) -> RgbaImage {
+    let (w, h) = img.dimensions();
+    let mut out = RgbaImage::new(w * 2, h * 2);
+    for y in 0..h as i32 {
+        for x in 0..w as i32 {
+            let e = px(img, x, y);
+            let (ox, oy) = (x as u32 * 2, y as u32 * 2);
+            for (sx, sy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                let horiz = px(img, x + sx, y);
+                let vert = px(img, x, y + sy);
+                let diag = px(img, x + sx, y + sy);
+                let p = if horiz == diag && vert == diag { diag } else { e };
+                out.put_pixel(ox + (sx > 0) as u32, oy + (sy > 0) as u32, p);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const A: Rgba<u8> = Rgba([255, 255, 255, 255]);
+    const B: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+    fn image(rows: [[Rgba<u8>; 3]; 3]) -> RgbaImage {
+        RgbaImage::from_fn(3, 3, |x, y| rows[y as usize][x as usize])
+    }
+
+    // The 2x2 (or 3x3) block the center pixel expanded to, row-major
+    fn center_block(out: &RgbaImage, n: u32) -> Vec<Rgba<u8>> {
+        (0..n * n).map(|k| *out.get_pixel(n + k % n, n + k / n)).collect()
+    }
+
+    #[test]
+    fn scale2x_fills_corners_where_edge_neighbours_agree() {
+        // Center B with A above and to the left: only the top-left corner turns A
+        let img = image([[A, A, B], [A, B, B], [B, B, B]]);
+        let out = scale2x(&img);
+        assert_eq!(out.dimensions(), (6, 6));
+        assert_eq!(center_block(&out, 2), [A, B, B, B]);
+        // A vertical line (B above and below match) is left alone
+        let line = image([[A, B, A], [A, B, A], [A, B, A]]);
+        assert_eq!(center_block(&scale2x(&line), 2), [B; 4]);
+    }
+
+    #[test]
+    fn scale3x_fills_the_matching_corner_and_its_edges() {
+        let img = image([[A, A, B], [A, B, B], [B, B, B]]);
+        let out = scale3x(&img);
+        assert_eq!(out.dimensions(), (9, 9));
+        assert_eq!(center_block(&out, 3), [A, B, B, B, B, B, B, B, B]);
+    }
+
+    #[test]
+    fn eagle_takes_a_corner_only_when_all_three_outer_neighbours_agree() {
+        // Top row and left column A: the top-left corner's three neighbours are all A
+        let img = image([[A, A, A], [A, B, B], [A, B, B]]);
+        let out = eagle2x(&img);
+        assert_eq!(out.dimensions(), (6, 6));
+        assert_eq!(center_block(&out, 2), [A, B, B, B]);
+        // Two of three agreeing isn't enough
+        let img = image([[B, A, A], [A, B, B], [A, B, B]]);
+        assert_eq!(center_block(&eagle2x(&img), 2), [B; 4]);
+    }
+}
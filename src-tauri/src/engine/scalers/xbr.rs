@@ -0,0 +1,102 @@
+use image::{Rgba, RgbaImage};
+
+use super::px;
+
+// Super-xBR interpolation weights
+const WGT1: f32 = 0.129633;
+const WGT2: f32 = 0.175068;
+const W1: f32 = -WGT1;
+const W2: f32 = WGT1 + 0.5;
+const W3: f32 = -WGT2;
+const W4: f32 = WGT2 + 0.5;
+// Edge-detection weights for passes 1 and 3, and for pass 2
+const WP_DIAGONAL: [f32; 6] = [2.0, 1.0, -1.0, 4.0, -1.0, 1.0];
+const WP_ORTHOGONAL: [f32; 6] = [2.0, 0.0, 0.0, 0.0, 0.0, 0.0];
+
+// 4x4 sample window per channel (R, G, B, A, luma), indexed [column][row]
+type Window = [[[f32; 4]; 4]; 5];
+
+fn window(img: &RgbaImage, coords: impl Fn(i32, i32) -> (i32, i32), offset: i32) -> Window {
+    let mut m = [[[0.0f32; 4]; 4]; 5];
+    for k in 0..16 {
+        let (sx, sy) = (k / 4, k % 4);
+        let (cx, cy) = coords(sx as i32 + offset, sy as i32 + offset);
+        let p = px(img, cx, cy).0;
+        for ch in 0..4 { m[ch][sx][sy] = p[ch] as f32; }
+        m[4][sx][sy] = 0.2126 * p[0] as f32 + 0.7152 * p[1] as f32 + 0.0722 * p[2] as f32;
+    }
+    m
+}
+
+fn diagonal_edge(m: &[[f32; 4]; 4], wp: &[f32; 6]) -> f32 {
+    let df = |a: f32, b: f32| (a - b).abs();
+    let dw1 = wp[0] * (df(m[0][2], m[1][1]) + df(m[1][1], m[2][0]) + df(m[1][3], m[2][2]) + df(m[2][2], m[3][1]))
+        + wp[1] * (df(m[0][3], m[1][2]) + df(m[2][1], m[3][0]))
+        + wp[2] * (df(m[0][3], m[2][1]) + df(m[1][2], m[3][0]))
+        + wp[3] * df(m[1][2], m[2][1])
+        + wp[4] * (df(m[0][2], m[2][0]) + df(m[1][3], m[3][1]))
+        + wp[5] * (df(m[0][1], m[1][0]) + df(m[2][3], m[3][2]));
+    let dw2 = wp[0] * (df(m[0][1], m[1][2]) + df(m[1][2], m[2][3]) + df(m[1][0], m[2][1]) + df(m[2][1], m[3][2]))
+        + wp[1] * (df(m[0][0], m[1][1]) + df(m[2][2], m[3][3]))
+        + wp[2] * (df(m[0][0], m[2][2]) + df(m[1][1], m[3][3]))
+        + wp[3] * df(m[1][1], m[2][2])
+        + wp[4] * (df(m[1][0], m[3][2]) + df(m[0][1], m[2][3]))
+        + wp[5] * (df(m[0][2], m[1][3]) + df(m[2][0], m[3][1]));
+    dw1 - dw2
+}
+
+// Interpolates along the weaker diagonal, clamped to the central four samples (anti-ringing)
+fn interpolate(m: &Window, wp: &[f32; 6], wa: f32, wb: f32) -> Rgba<u8> {
+    let d_edge = diagonal_edge(&m[4], wp);
+    let mut out = [0u8; 4];
+    for (ch, o) in out.iter_mut().enumerate() {
+        let c = &m[ch];
+        let v = if d_edge <= 0.0 {
+            wa * (c[0][3] + c[3][0]) + wb * (c[1][2] + c[2][1])
+        } else {
+            wa * (c[0][0] + c[3][3]) + wb * (c[1][1] + c[2][2])
+        };
+        let lo = c[1][1].min(c[2][1]).min(c[1][2]).min(c[2][2]);
+        let hi = c[1][1].max(c[2][1]).max(c[1][2]).max(c[2][2]);
+        *o = v.clamp(lo, hi).ceil().clamp(0.0, 255.0) as u8;
+    }
+    Rgba(out)
+}
+
+/// Super-xBR 2x (Hyllian): a diagonal pass fills the new center pixels, an orthogonal pass
+/// fills the edge pixels, and a final diagonal pass refines the whole image in place.
+pub fn super_xbr2x(img: &RgbaImage) -> RgbaImage {
+    let (w, h) = img.dimensions();
+    let (ow, oh) = (w * 2, h * 2);
+    let mut out = RgbaImage::new(ow, oh);
+    for y in (0..oh as i32).step_by(2) {
+        for x in (0..ow as i32).step_by(2) {
+            let (cx, cy) = (x / 2, y / 2);
+            let m = window(img, |sx, sy| (cx + sx, cy + sy), -1);
+            let e = px(img, cx, cy);
+            let (ux, uy) = (x as u32, y as u32);
+            out.put_pixel(ux, uy, e);
+            out.put_pixel(ux + 1, uy, e);
+            out.put_pixel(ux, uy + 1, e);
+            out.put_pixel(ux + 1, uy + 1, interpolate(&m, &WP_DIAGONAL, W1, W2));
+        }
+    }
+    for y in (0..oh as i32).step_by(2) {
+        for x in (0..ow as i32).step_by(2) {
+            let m = window(&out, |sx, sy| (sx + sy + x, sx - sy + y), -1);
+            let p = interpolate(&m, &WP_ORTHOGONAL, W3, W4);
+            out.put_pixel(x as u32 + 1, y as u32, p);
+            let m = window(&out, |sx, sy| (sx + sy - 1 + x, sx - sy + 1 + y), -1);
+            let p = interpolate(&m, &WP_ORTHOGONAL, W3, W4);
+            out.put_pixel(x as u32, y as u32 + 1, p);
+        }
+    }
+    for y in (0..oh as i32).rev() {
+        for x in (0..ow as i32).rev() {
+            let m = window(&out, |sx, sy| (sx + x, sy + y), -2);
+            let p = interpolate(&m, &WP_DIAGONAL, W1, W2);
+            out.put_pixel(x as u32, y as u32, p);
+        }
+    }
+    out
+}
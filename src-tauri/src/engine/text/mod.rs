@@ -0,0 +1,140 @@
+pub mod font;
+
+use super::algorithms::RgbaImage;
+use super::color::brightness;
+use font::glyph;
+
+/// Glyphs from empty to dense, used when no charset is given.
+pub const DEFAULT_CHARSET: &str = " .:-=+*#%@";
+/// Glyph cell size in the built-in font
+pub const CELL: u32 = 8;
+// Weight of glyph shape against tone when scoring a glyph for a textured cell
+const STRUCTURE_WEIGHT: f32 = 0.35;
+
+/// Text output encodings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+    /// Plain ASCII, no color
+    Ascii,
+    /// ANSI escapes with the 16 standard terminal colors
+    Ansi16,
+    /// ANSI escapes with the xterm 256-color cube and gray ramp
+    Ansi256,
+    /// ANSI escapes with 24-bit color
+    TrueColor,
+    /// A `<pre>` block with colored spans
+    Html,
+}
+
+impl TextFormat {
+    pub fn from_name(name: Option<&str>) -> Self {
+        match name.map(|s| s.trim().to_ascii_lowercase().replace(['-', ' '], "_")) {
+            Some(s) if s == "ansi16" || s == "ansi" => Self::Ansi16,
+            Some(s) if s == "ansi256" => Self::Ansi256,
+            Some(s) if s == "truecolor" || s == "ansi_truecolor" || s == "24bit" => Self::TrueColor,
+            Some(s) if s == "html" => Self::Html,
+            _ => Self::Ascii,
+        }
+    }
+}
+
+/// One character cell: its glyph and palette color (None where the grid is transparent).
+#[derive(Debug, Clone, Copy)]
+pub struct TextCell {
+    pub glyph: char,
+    pub color: Option<[u8; 3]>,
+}
+
+/// A grid of character cells, row-major.
+pub struct TextGrid {
+    pub columns: u32,
+    pub rows: u32,
+    pub cells: Vec<TextCell>,
+    /// Glyphs are drawn dark on a light background rather than light on dark
+    pub dark_on_light: bool,
+}
+
+struct Glyph {
+    ch: char,
+    // Pixel coverage minus the glyph's mean, row-major
+    shape: [f32; 64],
+    norm: f32,
+    coverage: f32,
+}
+
+// Charset glyphs the built-in font can draw, in the given order without repeats
+fn charset_glyphs(charset: &str) -> Vec<Glyph> {
+    let mut out: Vec<Glyph> = Vec::new();
+    for ch in charset.chars() {
+        let Some(rows) = glyph(ch) else { continue };
+        if out.iter().any(|g| g.ch == ch) {
+            continue;
+        }
+        let mut px = [0.0f32; 64];
+        for (y, row) in rows.iter().enumerate() {
+            for x in 0..8 {
+                px[y * 8 + x] = ((row >> x) & 1) as f32;
+            }
+        }
+        let coverage = px.iter().sum::<f32>() / 64.0;
+        let shape = px.map(|v| v - coverage);
+        let norm = shape.iter().map(|v| v * v).sum::<f32>().sqrt();
+        out.push(Glyph { ch, shape, norm, coverage });
+    }
+    out
+}
+
+/// Picks a glyph per cell of `colors` (the quantized grid, which also supplies each cell's
+/// color) from `charset`, falling back to [`DEFAULT_CHARSET`] when it has no drawable
+/// characters. `detail` is the image at the font's 8x8 pixels per cell: each block's mean
+/// luminance is mapped onto the charset's coverage range, and its shape is compared with each
+/// glyph's by correlation, weighted by how much contrast the block has, so flat cells pick
+/// purely on coverage.
+pub fn match_glyphs(colors: &RgbaImage, detail: &RgbaImage, charset: &str, dark_on_light: bool) -> TextGrid {
+    let mut glyphs = charset_glyphs(charset);
+    if glyphs.is_empty() {
+        glyphs = charset_glyphs(DEFAULT_CHARSET);
+    }
+    let (min_cov, max_cov) = glyphs
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), g| (lo.min(g.coverage), hi.max(g.coverage)));
+    let (columns, rows) = colors.dimensions();
+    // Ink coverage a luminance asks for: bright cells get dense glyphs on a dark background
+    let ink = |l: f32| if dark_on_light { 1.0 - l } else { l };
+
+    let mut cells = Vec::with_capacity((columns * rows) as usize);
+    for cy in 0..rows {
+        for cx in 0..columns {
+            let c = colors.get_pixel(cx, cy).0;
+            if c[3] == 0 {
+                cells.push(TextCell { glyph: ' ', color: None });
+                continue;
+            }
+            let mut block = [0.0f32; 64];
+            for (k, v) in block.iter_mut().enumerate() {
+                let p = detail.get_pixel(cx * CELL + k as u32 % CELL, cy * CELL + k as u32 / CELL).0;
+                *v = ink(brightness(p[0], p[1], p[2]));
+            }
+            let mean = block.iter().sum::<f32>() / 64.0;
+            let target = min_cov + mean * (max_cov - min_cov);
+            let shape = block.map(|v| v - mean);
+            let norm = shape.iter().map(|v| v * v).sum::<f32>().sqrt();
+            let contrast = norm / 8.0;
+            let score = |g: &Glyph| {
+                let corr = if norm > 0.0 && g.norm > 0.0 {
+                    shape.iter().zip(&g.shape).map(|(a, b)| a * b).sum::<f32>() / (norm * g.norm)
+                } else {
+                    0.0
+                };
+                (target - g.coverage).abs() + STRUCTURE_WEIGHT * contrast * (1.0 - corr)
+            };
+            let best = glyphs
+                .iter()
+                .min_by(|a, b| score(a).total_cmp(&score(b)))
+                .map(|g| g.ch)
+                .unwrap_or(' ');
+            cells.push(TextCell { glyph: best, color: Some([c[0], c[1], c[2]]) });
+        }
+    }
+    TextGrid { columns, rows, cells, dark_on_light }
+}
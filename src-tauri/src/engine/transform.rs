@@ -0,0 +1,140 @@
+use image::{imageops, Rgba, Rgba32FImage};
+
+use crate::types::{CropRect, Transform};
+
+/// Applies the request's geometry edits to the working image in order: crop, perspective,
+/// rotation, flip. Crop and perspective both use source coordinates.
+pub fn apply_transform(img: Rgba32FImage, t: &Transform) -> Rgba32FImage {
+    let mut img = img;
+    let mut origin = [0.0f32; 2];
+    if let Some(rect) = t.crop {
+        if let Some(cropped) = crop(&img, rect) {
+            origin = [rect.x as f32, rect.y as f32];
+            img = cropped;
+        }
+    }
+    if let Some(quad) = t.perspective {
+        let local = quad.map(|[x, y]| [x - origin[0], y - origin[1]]);
+        if let Some(rectified) = rectify_perspective(&img, local) {
+            img = rectified;
+        }
+    }
+    if let Some(deg) = t.rotate_degrees.filter(|d| d.is_finite()) {
+        img = rotate(img, deg);
+    }
+    if t.flip_horizontal { imageops::flip_horizontal_in_place(&mut img); }
+    if t.flip_vertical { imageops::flip_vertical_in_place(&mut img); }
+    img
+}
+
+// Clamped to the image bounds; None when the clamped rect is empty
+fn crop(img: &Rgba32FImage, rect: CropRect) -> Option<Rgba32FImage> {
+    let (w, h) = img.dimensions();
+    let x = rect.x.min(w);
+    let y = rect.y.min(h);
+    let cw = rect.width.min(w - x);
+    let ch = rect.height.min(h - y);
+    if cw == 0 || ch == 0 { return None; }
+    Some(imageops::crop_imm(img, x, y, cw, ch).to_image())
+}
+
+fn rotate(img: Rgba32FImage, degrees: f32) -> Rgba32FImage {
+    let deg = degrees.rem_euclid(360.0);
+    let quarter = (deg / 90.0).round();
+    if (deg - quarter * 90.0).abs() < 0.01 {
+        return match quarter as u32 % 4 {
+            1 => imageops::rotate90(&img),
+            2 => imageops::rotate180(&img),
+            3 => imageops::rotate270(&img),
+            _ => img,
+        };
+    }
+    let (sin, cos) = deg.to_radians().sin_cos();
+    let (w, h) = (img.width() as f32, img.height() as f32);
+    let out_w = (w * cos.abs() + h * sin.abs()).ceil().max(1.0) as u32;
+    let out_h = (w * sin.abs() + h * cos.abs()).ceil().max(1.0) as u32;
+    let (cx, cy) = (w / 2.0, h / 2.0);
+    let (ox, oy) = (out_w as f32 / 2.0, out_h as f32 / 2.0);
+    // Inverse mapping: rotate each output pixel center back into the source
+    Rgba32FImage::from_fn(out_w, out_h, |x, y| {
+        let dx = x as f32 + 0.5 - ox;
+        let dy = y as f32 + 0.5 - oy;
+        sample_bilinear(&img, cx + dx * cos + dy * sin, cy - dx * sin + dy * cos)
+    })
+}
+
+// Projective map of the unit square onto a quad (Heckbert), as [a, b, c, d, e, f, g, h]:
+// x = (a u + b v + c) / (g u + h v + 1), y = (d u + e v + f) / (g u + h v + 1)
+fn square_to_quad(q: [[f32; 2]; 4]) -> Option<[f32; 8]> {
+    let [[x0, y0], [x1, y1], [x2, y2], [x3, y3]] = q;
+    let (dx1, dx2, dx3) = (x1 - x2, x3 - x2, x0 - x1 + x2 - x3);
+    let (dy1, dy2, dy3) = (y1 - y2, y3 - y2, y0 - y1 + y2 - y3);
+    let (g, h) = if dx3.abs() < 1e-6 && dy3.abs() < 1e-6 {
+        (0.0, 0.0)
+    } else {
+        let det = dx1 * dy2 - dx2 * dy1;
+        if det.abs() < 1e-9 { return None; }
+        ((dx3 * dy2 - dx2 * dy3) / det, (dx1 * dy3 - dx3 * dy1) / det)
+    };
+    Some([
+        x1 - x0 + g * x1,
+        x3 - x0 + h * x3,
+        x0,
+        y1 - y0 + g * y1,
+        y3 - y0 + h * y3,
+        y0,
+        g,
+        h,
+    ])
+}
+
+fn dist(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+// Output size follows the longer of each pair of opposite edges
+fn rectify_perspective(img: &Rgba32FImage, quad: [[f32; 2]; 4]) -> Option<Rgba32FImage> {
+    if quad.iter().flatten().any(|v| !v.is_finite()) { return None; }
+    let [tl, tr, br, bl] = quad;
+    let out_w = dist(tl, tr).max(dist(bl, br)).round() as u32;
+    let out_h = dist(tl, bl).max(dist(tr, br)).round() as u32;
+    if out_w == 0 || out_h == 0 { return None; }
+    let [a, b, c, d, e, f, g, h] = square_to_quad(quad)?;
+    Some(Rgba32FImage::from_fn(out_w, out_h, |x, y| {
+        let u = (x as f32 + 0.5) / out_w as f32;
+        let v = (y as f32 + 0.5) / out_h as f32;
+        let den = g * u + h * v + 1.0;
+        if den.abs() < 1e-9 { return Rgba([0.0; 4]); }
+        sample_bilinear(img, (a * u + b * v + c) / den, (d * u + e * v + f) / den)
+    }))
+}
+
+// Bilinear sample at a continuous position (pixel centers at +0.5). Weights are premultiplied
+// by alpha and pixels outside the image count as transparent, so edges don't darken.
+fn sample_bilinear(img: &Rgba32FImage, x: f32, y: f32) -> Rgba<f32> {
+    let (w, h) = (img.width() as i64, img.height() as i64);
+    let fx = x - 0.5;
+    let fy = y - 0.5;
+    let x0 = fx.floor() as i64;
+    let y0 = fy.floor() as i64;
+    let tx = fx - x0 as f32;
+    let ty = fy - y0 as f32;
+    let mut acc = [0.0f32; 4];
+    for (dx, dy, wgt) in [
+        (0, 0, (1.0 - tx) * (1.0 - ty)),
+        (1, 0, tx * (1.0 - ty)),
+        (0, 1, (1.0 - tx) * ty),
+        (1, 1, tx * ty),
+    ] {
+        let (sx, sy) = (x0 + dx, y0 + dy);
+        if wgt <= 0.0 || sx < 0 || sy < 0 || sx >= w || sy >= h { continue; }
+        let p = img.get_pixel(sx as u32, sy as u32).0;
+        let wa = wgt * p[3];
+        acc[0] += p[0] * wa;
+        acc[1] += p[1] * wa;
+        acc[2] += p[2] * wa;
+        acc[3] += wa;
+    }
+    if acc[3] <= 1e-6 { return Rgba([0.0; 4]); }
+    Rgba([acc[0] / acc[3], acc[1] / acc[3], acc[2] / acc[3], acc[3].min(1.0)])
+}
@@ -1,10 +1,20 @@
 mod engine;
 mod types;
 
-use engine::pipeline::{render_base_png, render_preview_png, render_base_png_with_palette, render_preview_png_with_palette};
+use engine::pipeline::{render_base_png, render_preview_png, render_base_png_with_palette, render_preview_png_with_palette, render_hardware_export, render_text_output, render_text_output_with_palette};
 use engine::filters::render_filters_preview_png;
+use engine::luts::{load_luts, resolve_lut_path};
 use engine::palettes::{load_palettes, resolve_palette};
-use types::{RenderRequest, FilterChainRequest};
+use types::{RenderRequest, FilterChainRequest, HardwareExport};
+
+// Fills in `lut_path` from `lut_name`; an unknown name fails the render instead of
+// silently skipping the LUT
+fn resolve_request_lut(app: &tauri::AppHandle, req: &mut RenderRequest) -> Result<(), String> {
+    if let Some(name) = req.lut_name.as_deref().filter(|n| !n.trim().is_empty()) {
+        req.lut_path = Some(resolve_lut_path(app, name).ok_or_else(|| format!("unknown LUT: {name}"))?);
+    }
+    Ok(())
+}
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -12,6 +22,8 @@ async fn render_preview(app: tauri::AppHandle, req: RenderRequest) -> Result<Str
     // Run heavy processing in a blocking task to avoid blocking the async runtime
     let app_clone = app.clone();
     tokio::task::spawn_blocking(move || {
+        let mut req = req;
+        resolve_request_lut(&app_clone, &mut req)?;
         if let Some(name) = req.palette_name.clone() {
             let p = resolve_palette(&app_clone, &name);
             if !p.colors.is_empty() {
@@ -31,6 +43,8 @@ async fn render_filters_preview(req: RenderRequest) -> Result<String, String> {
         image_data_url: req.image_data_url,
         display_size: req.display_size,
         steps: Vec::new(),
+        export_filter: None,
+        export_scale_factor: None,
     };
     tokio::task::spawn_blocking(move || {
         render_filters_preview_png(chain).map_err(|e| e.to_string())
@@ -52,6 +66,8 @@ async fn render_filters_chain_preview(req: FilterChainRequest) -> Result<String,
 async fn render_base(app: tauri::AppHandle, req: RenderRequest) -> Result<String, String> {
     let app_clone = app.clone();
     tokio::task::spawn_blocking(move || {
+        let mut req = req;
+        resolve_request_lut(&app_clone, &mut req)?;
         if let Some(name) = req.palette_name.clone() {
             let p = resolve_palette(&app_clone, &name);
             if !p.colors.is_empty() {
@@ -64,6 +80,40 @@ async fn render_base(app: tauri::AppHandle, req: RenderRequest) -> Result<String
     .unwrap_or_else(|e| Err(format!("Task join error: {}", e)))
 }
 
+#[tauri::command]
+async fn render_hardware(app: tauri::AppHandle, req: RenderRequest) -> Result<HardwareExport, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut req = req;
+        resolve_request_lut(&app, &mut req)?;
+        // Fixed-palette modes ignore it; the Game Boy mode takes its shades from it
+        let palette = req
+            .palette_name
+            .clone()
+            .map(|name| resolve_palette(&app, &name).colors)
+            .unwrap_or_default();
+        render_hardware_export(req, palette).map_err(|e| e.to_string())
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task join error: {}", e)))
+}
+
+#[tauri::command]
+async fn render_text(app: tauri::AppHandle, req: RenderRequest) -> Result<String, String> {
+    tokio::task::spawn_blocking(move || {
+        let mut req = req;
+        resolve_request_lut(&app, &mut req)?;
+        if let Some(name) = req.palette_name.clone() {
+            let p = resolve_palette(&app, &name);
+            if !p.colors.is_empty() {
+                return render_text_output_with_palette(req, p.colors).map_err(|e| e.to_string());
+            }
+        }
+        render_text_output(req).map_err(|e| e.to_string())
+    })
+    .await
+    .unwrap_or_else(|e| Err(format!("Task join error: {}", e)))
+}
+
 #[tauri::command]
 async fn list_palettes(app: tauri::AppHandle) -> Vec<(String, Vec<[u8;3]>)> {
     // This is fast, but keep async for consistency
@@ -77,11 +127,20 @@ async fn list_palettes(app: tauri::AppHandle) -> Vec<(String, Vec<[u8;3]>)> {
     .unwrap_or_default()
 }
 
+#[tauri::command]
+async fn list_luts(app: tauri::AppHandle) -> Vec<String> {
+    tokio::task::spawn_blocking(move || {
+        load_luts(&app).into_iter().map(|l| l.name).collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![render_preview, render_base, list_palettes, render_filters_preview, render_filters_chain_preview])
+        .invoke_handler(tauri::generate_handler![render_preview, render_base, list_palettes, list_luts, render_filters_preview, render_filters_chain_preview, render_hardware, render_text])
         .setup(|_app| {
             // Initialize async runtime if needed
             Ok(())
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RenderRequest {
@@ -7,7 +8,9 @@ pub struct RenderRequest {
     /// Grid width and height (e.g. 32x32, 384x192)
     pub grid_width: u32,
     pub grid_height: u32,
-    /// Optional grid string like "32" or "384x192". If present, Rust parses it.
+    /// Optional grid string like "32", "384x192" or "auto" (detect native grid of upscaled pixel
+    /// art; the render fails when none is found).
+    /// If present, Rust parses it.
     #[serde(default)]
     pub grid_value: Option<String>,
     /// Algorithm name (e.g. "Standard", "Floyd–Steinberg", etc.)
@@ -16,21 +19,165 @@ pub struct RenderRequest {
     pub palette_name: Option<String>,
     /// Desired preview size; upscaled image width/height (nearest multiple of grid)
     pub display_size: Option<u32>,
+    /// Optional pixel aspect ratio (pixel width / height, 1.0 = square). E.g. 2.0 for C64
+    /// multicolor wide pixels. The grid then describes the on-screen size; the base render
+    /// stays at logical resolution and previews upscale each axis separately.
+    #[serde(default)]
+    pub pixel_aspect: Option<f32>,
+    /// Optional fixed-hardware target: "zx_spectrum", "nes", "c64_hires", "c64_multicolor",
+    /// "ham6", "ham8" or "gameboy", or a direct-color framebuffer: "rgb332", "rgb444",
+    /// "rgb555", "rgb565" or "grayN" (N levels), or an embedded display buffer:
+    /// "mono_horizontal", "mono_vertical", "mono_page" (SSD1306), "gray_2bpp", "rgb565_be",
+    /// "rgb565_le" or "bwr" (three-color e-paper), or an ESC/POS thermal print job:
+    /// "escpos_384", "escpos_576" or "escpos_832" (dot width; height follows the source).
+    /// Overrides the palette (Game Boy takes four shades from it) and, for the fixed-screen
+    /// machines, the grid and pixel aspect; quantizes under the machine's color constraints
+    /// using `algorithm`
+    #[serde(default)]
+    pub hardware_mode: Option<String>,
+    /// Optional palette variant for hardware modes that have several (C64: "pepto" default or
+    /// "colodore")
+    #[serde(default)]
+    pub hardware_palette: Option<String>,
+    /// Optional output for embedded display modes: "c" (header, default), "rust" or "bin"
+    #[serde(default)]
+    pub hardware_output: Option<String>,
+    /// Optional geometric transform (crop, perspective, rotation, flip) applied to the source
+    /// before any prefilter
+    #[serde(default)]
+    pub transform: Option<Transform>,
+    /// Optional tone mapping for HDR (OpenEXR / Radiance / float TIFF) sources: "reinhard"
+    /// (default), "aces" or "clip". Ignored for 8/16-bit sources.
+    #[serde(default)]
+    pub hdr_tone_map: Option<String>,
+    /// Optional exposure in stops applied to HDR sources before tone mapping (0.0 = unchanged)
+    #[serde(default)]
+    pub hdr_exposure: Option<f32>,
+    /// Optional resampling for the upscaled preview/export: "nearest" (default) or a pixel-art
+    /// scaler: "scale2x".."scale4x", "eagle" or "super-xbr". Family names without a factor
+    /// ("scalenx", "eagle", "super-xbr") use `export_scale_factor`
+    #[serde(default)]
+    pub export_filter: Option<String>,
+    /// Optional pixel-art scaler factor (2..4) when `export_filter` doesn't name one
+    #[serde(default)]
+    pub export_scale_factor: Option<u32>,
     /// Optional tone curve gamma (1.0 = no change). Typical 0.5..2.0
     #[serde(default)]
     pub tone_gamma: Option<f32>,
+    /// Optional per-channel levels (black point, white point, midtone) for the tone stage
+    #[serde(default)]
+    pub levels: Option<Levels>,
+    /// Optional monotone spline tone curves given as control points
+    #[serde(default)]
+    pub tone_curves: Option<ToneCurves>,
+    /// Stretch each channel to its source histogram range before levels and curves
+    #[serde(default)]
+    pub auto_levels: Option<bool>,
     /// Optional denoise sigma for Gaussian blur in source domain. Typical 0.0..2.5
     #[serde(default)]
     pub denoise_sigma: Option<f32>,
+    /// Optional edge-preserving smoothing at the denoise stage: "bilateral", "kuwahara" or "guided"
+    #[serde(default)]
+    pub edge_preserve_filter: Option<String>,
+    /// Neighborhood radius in grid pixels for edge-preserving smoothing (default 2)
+    #[serde(default)]
+    pub edge_preserve_radius: Option<u32>,
+    /// Edge sensitivity 0..1; higher values smooth across stronger edges (default 0.3)
+    #[serde(default)]
+    pub edge_preserve_strength: Option<f32>,
+    /// Optional unsharp mask amount (0.0 = off). Typical 0.5..2.0
+    #[serde(default)]
+    pub sharpen_amount: Option<f32>,
+    /// Unsharp mask gaussian radius (sigma) in pixels (default 1.0)
+    #[serde(default)]
+    pub sharpen_radius: Option<f32>,
+    /// Unsharp mask threshold in levels; smaller differences are not sharpened (default 0)
+    #[serde(default)]
+    pub sharpen_threshold: Option<u8>,
+    /// Optional Laplacian edge boost strength (0.0 = off). Typical 0.2..1.0
+    #[serde(default)]
+    pub edge_boost: Option<f32>,
+    /// Where sharpening runs: "source" (before grid downscale, default), "grid" or "both"
+    #[serde(default)]
+    pub sharpen_stage: Option<String>,
     /// Optional pre-contrast applied to source before grid/pixelize (1.0 = no change)
     #[serde(default)]
     pub pre_contrast: Option<f32>,
+    /// Optional .cube LUT (name from resources/luts or absolute path) applied as a prefilter
+    #[serde(default)]
+    pub lut_name: Option<String>,
+    /// LUT blend strength 0..1 (default 1.0)
+    #[serde(default)]
+    pub lut_strength: Option<f32>,
+    /// LUT interpolation: "tetrahedral" (default) or "trilinear"
+    #[serde(default)]
+    pub lut_interpolation: Option<String>,
+    /// Resolved LUT file; filled in by the command layer from `lut_name`
+    #[serde(skip)]
+    pub lut_path: Option<PathBuf>,
+    /// Optional reference image (data URL or absolute path) whose colors the source is shifted
+    /// toward
+    #[serde(default)]
+    pub reference_image: Option<String>,
+    /// Color transfer method: "reinhard" (Lab mean/variance, default) or "histogram"
+    #[serde(default)]
+    pub color_transfer: Option<String>,
+    /// Color transfer blend strength 0..1 (default 1.0)
+    #[serde(default)]
+    pub color_transfer_strength: Option<f32>,
+    /// Optional luminance equalization before grid/pixelize: "global" or "clahe"
+    #[serde(default)]
+    pub equalize: Option<String>,
+    /// CLAHE tile grid size per axis (default 8)
+    #[serde(default)]
+    pub clahe_tiles: Option<u32>,
+    /// CLAHE clip limit as a multiple of the mean bin count (default 2.0)
+    #[serde(default)]
+    pub clahe_clip_limit: Option<f32>,
     /// Optional pre-saturation applied to source before grid/pixelize (1.0 = no change)
     #[serde(default)]
     pub pre_saturation: Option<f32>,
     /// Optional pre-hue shift in degrees applied to source before grid/pixelize (0.0 = no change)
     #[serde(default)]
     pub pre_hue_degrees: Option<f32>,
+    /// Optional background removal to alpha: "color_key" (every pixel near the key color) or
+    /// "flood_fill" (only regions connected to the image border)
+    #[serde(default)]
+    pub background_removal: Option<String>,
+    /// Optional key color as hex (e.g. "#00ff00"); defaults to the median border color
+    #[serde(default)]
+    pub background_color: Option<String>,
+    /// Optional Lab distance (Delta E) within which a pixel counts as background. Typical 5..40
+    #[serde(default)]
+    pub background_tolerance: Option<f32>,
+    /// Optional extra Lab distance over which alpha ramps back to opaque, for soft edges
+    #[serde(default)]
+    pub background_feather: Option<f32>,
+    /// Optional 1px outline drawn after dithering at grid resolution: "outer" (on transparent or
+    /// darker pixels just outside the subject) or "inner" (on the subject's own edge pixels)
+    #[serde(default)]
+    pub outline: Option<String>,
+    /// Optional outline boundary source: "alpha" (default, opaque vs transparent) or "luminance"
+    #[serde(default)]
+    pub outline_source: Option<String>,
+    /// Optional luma difference (0..255) that counts as an edge for the luminance source.
+    /// Default 64
+    #[serde(default)]
+    pub outline_threshold: Option<u8>,
+    /// Optional palette index for the outline color; when unset the color is auto-darkened from
+    /// the neighboring subject pixels and snapped to the palette
+    #[serde(default)]
+    pub outline_palette_index: Option<usize>,
+    /// Optional neighborhood for outline adjacency: 4 (default) or 8
+    #[serde(default)]
+    pub outline_connectivity: Option<u8>,
+    /// Optional cleanup strength (0..1) after dithering: removes stray single pixels and L-shaped
+    /// corners on 1px lines while leaving dither patterns alone. 0 or unset disables
+    #[serde(default)]
+    pub cleanup_strength: Option<f32>,
+    /// Optional hex colors the cleanup pass never changes (e.g. eye highlights)
+    #[serde(default)]
+    pub cleanup_protected_colors: Option<Vec<String>>,
     /// Optional invert and night vision prefilter
     #[serde(default)]
     pub invert_colors: Option<bool>,
@@ -41,8 +188,106 @@ pub struct RenderRequest {
     pub add_black_to_palette: Option<bool>,
     #[serde(default)]
     pub add_white_to_palette: Option<bool>,
+    /// Alpha handling before quantization: "keep" (default), "threshold" (1-bit) or "dither"
+    /// (ordered 1-bit). Fully transparent pixels never take part in error diffusion.
+    #[serde(default)]
+    pub alpha_mode: Option<String>,
+    /// Cutoff for "threshold" alpha mode (default 128)
+    #[serde(default)]
+    pub alpha_threshold: Option<u8>,
+    /// Reserve index 0 as a transparent palette entry in indexed exports
+    #[serde(default)]
+    pub transparent_palette_slot: Option<bool>,
+    /// Write the base export as an indexed PNG instead of RGBA when it fits in 256 entries
+    #[serde(default)]
+    pub indexed_export: Option<bool>,
+    /// Text render encoding: "ascii" (default), "ansi16", "ansi256", "truecolor" or "html"
+    #[serde(default)]
+    pub text_format: Option<String>,
+    /// Characters the text render may use, any order; only printable ASCII is drawable.
+    /// Defaults to " .:-=+*#%@"
+    #[serde(default)]
+    pub text_charset: Option<String>,
+    /// Text render for light backgrounds: dense glyphs stand for dark cells instead of bright
+    #[serde(default)]
+    pub text_invert: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct ChannelLevels {
+    /// Input level mapped to 0
+    #[serde(default)]
+    pub black: u8,
+    /// Input level mapped to 255
+    #[serde(default = "default_white")]
+    pub white: u8,
+    /// Midtone gamma (1.0 = linear, > 1.0 brightens midtones)
+    #[serde(default = "default_midtone")]
+    pub midtone: f32,
 }
 
+fn default_white() -> u8 { 255 }
+fn default_midtone() -> f32 { 1.0 }
+
+impl Default for ChannelLevels {
+    fn default() -> Self {
+        Self { black: 0, white: default_white(), midtone: default_midtone() }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Levels {
+    /// Applied to all channels before the per-channel levels
+    #[serde(default)]
+    pub master: Option<ChannelLevels>,
+    #[serde(default)]
+    pub red: Option<ChannelLevels>,
+    #[serde(default)]
+    pub green: Option<ChannelLevels>,
+    #[serde(default)]
+    pub blue: Option<ChannelLevels>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ToneCurves {
+    /// Control points as [input, output] pairs in 0..255; fewer than two means identity
+    #[serde(default)]
+    pub master: Vec<[f32; 2]>,
+    #[serde(default)]
+    pub red: Vec<[f32; 2]>,
+    #[serde(default)]
+    pub green: Vec<[f32; 2]>,
+    #[serde(default)]
+    pub blue: Vec<[f32; 2]>,
+}
+
+/// Crop rectangle in source pixel coordinates (after EXIF orientation).
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Source geometry edits, applied in order: crop, perspective, rotation, flip.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Transform {
+    #[serde(default)]
+    pub crop: Option<CropRect>,
+    /// Four source points [x, y] (top-left, top-right, bottom-right, bottom-left) that are
+    /// rectified to an upright rectangle. Uses source coordinates, like `crop`.
+    #[serde(default)]
+    pub perspective: Option<[[f32; 2]; 4]>,
+    /// Clockwise rotation in degrees. Multiples of 90 are lossless; other angles resample
+    /// bilinearly onto an enlarged canvas with transparent corners.
+    #[serde(default)]
+    pub rotate_degrees: Option<f32>,
+    #[serde(default)]
+    pub flip_horizontal: bool,
+    #[serde(default)]
+    pub flip_vertical: bool,
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FilterStep {
@@ -69,5 +314,27 @@ pub struct FilterChainRequest {
     /// Ordered list of filter steps to apply
     #[serde(default)]
     pub steps: Vec<FilterStep>,
+    /// Optional pixel-art scaler for the display upscale (same names as `RenderRequest`)
+    #[serde(default)]
+    pub export_filter: Option<String>,
+    #[serde(default)]
+    pub export_scale_factor: Option<u32>,
 }
 
+
+/// Hardware-mode export: the base PNG plus the target's native files
+#[derive(Debug, Clone, Serialize)]
+pub struct HardwareExport {
+    pub png_data_url: String,
+    pub files: Vec<ExportedFile>,
+    /// Hardware limits the result exceeds (e.g. too many unique tiles)
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportedFile {
+    /// Suggested file name, e.g. "screen.scr"
+    pub file_name: String,
+    /// File contents as a base64 data URL
+    pub data_url: String,
+}
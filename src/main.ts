@@ -6,32 +6,13 @@ type PaletteTuple = [string, number[][]];
 const qs = <T extends HTMLElement>(sel: string) => document.querySelector(sel) as T | null;
 
 /**
- * Loads an image file and returns a properly-oriented data URL.
- * This fixes issues where images with EXIF orientation metadata appear rotated.
+ * Reads an image file as a data URL with its original bytes.
+ * EXIF orientation and embedded ICC profiles are applied by the Rust decode stage.
  */
-async function loadImageWithCorrectOrientation(file: File): Promise<string> {
+async function readFileAsDataURL(file: File): Promise<string> {
   return new Promise((resolve, reject) => {
     const reader = new FileReader();
-    reader.onload = (e) => {
-      const dataUrl = String(e.target?.result || "");
-      const img = new Image();
-      img.onload = () => {
-        // Create canvas and draw image (browsers auto-apply EXIF orientation)
-        const canvas = document.createElement("canvas");
-        canvas.width = img.naturalWidth;
-        canvas.height = img.naturalHeight;
-        const ctx = canvas.getContext("2d");
-        if (!ctx) {
-          reject(new Error("Could not get canvas context"));
-          return;
-        }
-        ctx.drawImage(img, 0, 0);
-        // Convert back to data URL with correct orientation
-        resolve(canvas.toDataURL("image/png"));
-      };
-      img.onerror = () => reject(new Error("Failed to load image"));
-      img.src = dataUrl;
-    };
+    reader.onload = (e) => resolve(String(e.target?.result || ""));
     reader.onerror = () => reject(new Error("Failed to read file"));
     reader.readAsDataURL(file);
   });
@@ -77,6 +58,18 @@ async function loadPalettes() {
   return sorted;
 }
 
+async function loadLuts() {
+  const names = (await invoke("list_luts")) as string[];
+  const sel = qs<HTMLSelectElement>("#lut");
+  if (!sel) return;
+  for (const name of names) {
+    const opt = document.createElement("option");
+    opt.value = name;
+    opt.textContent = name;
+    sel.appendChild(opt);
+  }
+}
+
 window.addEventListener("DOMContentLoaded", async () => {
   // Set up simple in-app routing between Pixelizer and Filters pages
   mountRoutes();
@@ -90,6 +83,7 @@ window.addEventListener("DOMContentLoaded", async () => {
   const paletteSel = qs<HTMLSelectElement>("#palette");
   const algoSel = qs<HTMLSelectElement>("#algorithm");
   const gridSel = qs<HTMLSelectElement>("#grid");
+  const pixelAspectSel = qs<HTMLSelectElement>("#pixelAspect");
   const tone = qs<HTMLInputElement>("#tone");
   const toneLabel = qs<HTMLDivElement>("#toneLabel");
   const denoise = qs<HTMLInputElement>("#denoise");
@@ -103,10 +97,26 @@ window.addEventListener("DOMContentLoaded", async () => {
   const btnGen = qs<HTMLButtonElement>("#generate");
   const btnUpscaled = qs<HTMLButtonElement>("#download-upscaled");
   const btnBase = qs<HTMLButtonElement>("#download-base");
+  const btnHardware = qs<HTMLButtonElement>("#download-hardware");
+  const hardwareSel = qs<HTMLSelectElement>("#hardwareMode");
+  const hardwarePaletteSel = qs<HTMLSelectElement>("#hardwarePalette");
+  const hardwareOutputSel = qs<HTMLSelectElement>("#hardwareOutput");
+  const btnText = qs<HTMLButtonElement>("#download-text");
+  const textFormatSel = qs<HTMLSelectElement>("#textFormat");
+  const textCharset = qs<HTMLInputElement>("#textCharset");
+  const textInvert = qs<HTMLInputElement>("#textInvert");
   const optNightVision = qs<HTMLInputElement>("#optNightVision");
   const optAddBlack = qs<HTMLInputElement>("#optAddBlack");
   const optAddWhite = qs<HTMLInputElement>("#optAddWhite");
   const optInvert = qs<HTMLInputElement>("#optInvert");
+  const lutSel = qs<HTMLSelectElement>("#lut");
+  const lutStrength = qs<HTMLInputElement>("#lutStrength");
+  const lutStrengthLabel = qs<HTMLLabelElement>("#lutStrengthLabel");
+  const alphaModeSel = qs<HTMLSelectElement>("#alphaMode");
+  const alphaThreshold = qs<HTMLInputElement>("#alphaThreshold");
+  const alphaThresholdLabel = qs<HTMLLabelElement>("#alphaThresholdLabel");
+  const optIndexedExport = qs<HTMLInputElement>("#optIndexedExport");
+  const optTransparentSlot = qs<HTMLInputElement>("#optTransparentSlot");
   const exportScale = qs<HTMLSelectElement>("#exportScale");
   const exportFilter = qs<HTMLSelectElement>("#exportFilter");
   const exportClamp = qs<HTMLInputElement>("#exportClamp");
@@ -128,6 +138,7 @@ window.addEventListener("DOMContentLoaded", async () => {
   // no live rendering; only render when Pixelate is pressed
 
   await loadPalettes();
+  await loadLuts();
 
   function setPreview(src: string | null) {
     if (!output || !outputEmpty) return;
@@ -161,6 +172,8 @@ window.addEventListener("DOMContentLoaded", async () => {
     enable(btnGen, true);
     enable(btnUpscaled, !!upscaledDataURL);
     enable(btnBase, !!selectedImage);
+    enable(btnHardware, !!selectedImage && !!hardwareSel?.value);
+    enable(btnText, !!selectedImage);
   }
 
   function updateToneLabel() {
@@ -178,16 +191,26 @@ window.addEventListener("DOMContentLoaded", async () => {
   function updatePreHueLabel() {
     if (preHue && preHueLabel) preHueLabel.textContent = `Hue: ${Number(preHue.value).toFixed(0)}°`;
   }
+  function updateLutStrengthLabel() {
+    if (lutStrength && lutStrengthLabel) lutStrengthLabel.textContent = `Strength: ${Number(lutStrength.value).toFixed(2)}`;
+  }
+  function updateAlphaThresholdLabel() {
+    if (alphaThreshold && alphaThresholdLabel) alphaThresholdLabel.textContent = `Alpha cutoff: ${alphaThreshold.value}`;
+  }
   updateToneLabel();
   updateDenoiseLabel();
   updatePreContrastLabel();
   updatePreSaturationLabel();
   updatePreHueLabel();
+  updateLutStrengthLabel();
+  updateAlphaThresholdLabel();
   tone?.addEventListener("input", updateToneLabel);
   denoise?.addEventListener("input", updateDenoiseLabel);
   preContrast?.addEventListener("input", updatePreContrastLabel);
   preSaturation?.addEventListener("input", updatePreSaturationLabel);
   preHue?.addEventListener("input", updatePreHueLabel);
+  lutStrength?.addEventListener("input", updateLutStrengthLabel);
+  alphaThreshold?.addEventListener("input", updateAlphaThresholdLabel);
 
   async function renderNow() {
     if (!selectedImage || !algoSel || !gridSel || !paletteSel) return;
@@ -201,6 +224,9 @@ window.addEventListener("DOMContentLoaded", async () => {
         grid_width: 0,
         grid_height: 0,
         grid_value: val,
+        pixel_aspect: pixelAspectSel ? Number(pixelAspectSel.value) : undefined,
+        hardware_mode: hardwareSel?.value || undefined,
+        hardware_palette: hardwarePaletteSel?.value || undefined,
         algorithm: algoSel.value,
         palette_name: paletteSel.value,
         display_size: 1000,
@@ -209,10 +235,16 @@ window.addEventListener("DOMContentLoaded", async () => {
         pre_contrast: preContrast ? Number(preContrast.value) : undefined,
         pre_saturation: preSaturation ? Number(preSaturation.value) : undefined,
         pre_hue_degrees: preHue ? Number(preHue.value) : undefined,
+        lut_name: lutSel?.value || undefined,
+        lut_strength: lutStrength ? Number(lutStrength.value) : undefined,
+        alpha_mode: alphaModeSel?.value || undefined,
+        alpha_threshold: alphaThreshold ? Number(alphaThreshold.value) : undefined,
         add_black_to_palette: optAddBlack?.checked || false,
         add_white_to_palette: optAddWhite?.checked || false,
         invert_colors: optInvert?.checked || false,
         night_vision_prefilter: optNightVision?.checked || false,
+        export_scale_factor: exportScale ? Number(exportScale.value) : 2,
+        export_filter: exportFilter?.value || "nearest",
       };
       const up = (await invoke("render_preview", { req })) as string;
       if (mySeq !== renderCounter) return; // stale
@@ -231,7 +263,7 @@ window.addEventListener("DOMContentLoaded", async () => {
 
   async function handleFile(file: File) {
     try {
-      selectedImage = await loadImageWithCorrectOrientation(file);
+      selectedImage = await readFileAsDataURL(file);
       if (thumb && dropHint) {
         thumb.src = selectedImage;
         thumb.style.display = "";
@@ -283,6 +315,9 @@ window.addEventListener("DOMContentLoaded", async () => {
           grid_width: 0,
           grid_height: 0,
           grid_value: val,
+          pixel_aspect: pixelAspectSel ? Number(pixelAspectSel.value) : undefined,
+          hardware_mode: hardwareSel?.value || undefined,
+          hardware_palette: hardwarePaletteSel?.value || undefined,
           algorithm: algoSel.value,
           palette_name: paletteSel.value,
           display_size: 2000,
@@ -291,6 +326,10 @@ window.addEventListener("DOMContentLoaded", async () => {
           pre_contrast: preContrast ? Number(preContrast.value) : undefined,
           pre_saturation: preSaturation ? Number(preSaturation.value) : undefined,
           pre_hue_degrees: preHue ? Number(preHue.value) : undefined,
+          lut_name: lutSel?.value || undefined,
+          lut_strength: lutStrength ? Number(lutStrength.value) : undefined,
+          alpha_mode: alphaModeSel?.value || undefined,
+          alpha_threshold: alphaThreshold ? Number(alphaThreshold.value) : undefined,
           add_black_to_palette: optAddBlack?.checked || false,
           add_white_to_palette: optAddWhite?.checked || false,
           invert_colors: optInvert?.checked || false,
@@ -321,6 +360,9 @@ window.addEventListener("DOMContentLoaded", async () => {
             grid_width: 0,
             grid_height: 0,
             grid_value: val,
+            pixel_aspect: pixelAspectSel ? Number(pixelAspectSel.value) : undefined,
+            hardware_mode: hardwareSel?.value || undefined,
+            hardware_palette: hardwarePaletteSel?.value || undefined,
             algorithm: algoSel?.value || "Standard",
             palette_name: paletteSel?.value || undefined,
             tone_gamma: tone ? Number(tone.value) : undefined,
@@ -328,6 +370,12 @@ window.addEventListener("DOMContentLoaded", async () => {
             pre_contrast: preContrast ? Number(preContrast.value) : undefined,
             pre_saturation: preSaturation ? Number(preSaturation.value) : undefined,
             pre_hue_degrees: preHue ? Number(preHue.value) : undefined,
+            lut_name: lutSel?.value || undefined,
+            lut_strength: lutStrength ? Number(lutStrength.value) : undefined,
+            alpha_mode: alphaModeSel?.value || undefined,
+            alpha_threshold: alphaThreshold ? Number(alphaThreshold.value) : undefined,
+            indexed_export: optIndexedExport?.checked || false,
+            transparent_palette_slot: optTransparentSlot?.checked || false,
           };
           baseDataURL = (await invoke("render_base", { req })) as string;
         }
@@ -341,16 +389,124 @@ window.addEventListener("DOMContentLoaded", async () => {
     })();
   });
 
+  btnHardware?.addEventListener("click", (e) => {
+    const el = e.currentTarget as HTMLButtonElement;
+    el.classList.add("is-pressed");
+    setTimeout(() => el.classList.remove("is-pressed"), 90);
+    (async () => {
+      try {
+        if (!selectedImage || !hardwareSel?.value) return;
+        const req = {
+          image_data_url: selectedImage,
+          grid_width: 0,
+          grid_height: 0,
+          grid_value: gridSel?.value?.trim() || "32",
+          pixel_aspect: pixelAspectSel ? Number(pixelAspectSel.value) : undefined,
+          hardware_mode: hardwareSel.value,
+          hardware_palette: hardwarePaletteSel?.value || undefined,
+          hardware_output: hardwareOutputSel?.value || undefined,
+          algorithm: algoSel?.value || "Standard",
+          palette_name: paletteSel?.value || undefined,
+          tone_gamma: tone ? Number(tone.value) : undefined,
+          denoise_sigma: denoise ? Number(denoise.value) : undefined,
+          pre_contrast: preContrast ? Number(preContrast.value) : undefined,
+          pre_saturation: preSaturation ? Number(preSaturation.value) : undefined,
+          pre_hue_degrees: preHue ? Number(preHue.value) : undefined,
+          lut_name: lutSel?.value || undefined,
+          lut_strength: lutStrength ? Number(lutStrength.value) : undefined,
+          alpha_mode: alphaModeSel?.value || undefined,
+          alpha_threshold: alphaThreshold ? Number(alphaThreshold.value) : undefined,
+          indexed_export: optIndexedExport?.checked || false,
+          transparent_palette_slot: optTransparentSlot?.checked || false,
+          invert_colors: optInvert?.checked || false,
+          night_vision_prefilter: optNightVision?.checked || false,
+        };
+        const out = (await invoke("render_hardware", { req })) as {
+          png_data_url: string;
+          files: { file_name: string; data_url: string }[];
+          warnings: string[];
+        };
+        downloadDataURL(out.png_data_url, `bitcrush-${hardwareSel.value}.png`);
+        for (const f of out.files) downloadDataURL(f.data_url, `bitcrush-${f.file_name}`);
+        flashDownload(el);
+        if (out.warnings.length) alert(out.warnings.join("\n"));
+      } catch (err) {
+        console.error(err);
+      }
+    })();
+  });
+
+  btnText?.addEventListener("click", (e) => {
+    const el = e.currentTarget as HTMLButtonElement;
+    el.classList.add("is-pressed");
+    setTimeout(() => el.classList.remove("is-pressed"), 90);
+    (async () => {
+      try {
+        if (!selectedImage) return;
+        const format = textFormatSel?.value || "ascii";
+        const req = {
+          image_data_url: selectedImage,
+          grid_width: 0,
+          grid_height: 0,
+          grid_value: gridSel?.value?.trim() || "32",
+          algorithm: algoSel?.value || "Standard",
+          palette_name: paletteSel?.value || undefined,
+          tone_gamma: tone ? Number(tone.value) : undefined,
+          denoise_sigma: denoise ? Number(denoise.value) : undefined,
+          pre_contrast: preContrast ? Number(preContrast.value) : undefined,
+          pre_saturation: preSaturation ? Number(preSaturation.value) : undefined,
+          pre_hue_degrees: preHue ? Number(preHue.value) : undefined,
+          lut_name: lutSel?.value || undefined,
+          lut_strength: lutStrength ? Number(lutStrength.value) : undefined,
+          alpha_mode: alphaModeSel?.value || undefined,
+          alpha_threshold: alphaThreshold ? Number(alphaThreshold.value) : undefined,
+          text_format: format,
+          text_charset: textCharset?.value || undefined,
+          text_invert: textInvert?.checked || false,
+        };
+        const out = (await invoke("render_text", { req })) as string;
+        const ext = format === "html" ? "html" : format === "ascii" ? "txt" : "ans";
+        const mime = format === "html" ? "text/html" : "text/plain";
+        downloadDataURL(`data:${mime};charset=utf-8,${encodeURIComponent(out)}`, `bitcrush.${ext}`);
+        flashDownload(el);
+      } catch (err) {
+        console.error(err);
+      }
+    })();
+  });
+
   // Mark dirty on control changes, but do not auto-render
   paletteSel?.addEventListener("change", markDirty);
   algoSel?.addEventListener("change", markDirty);
   gridSel?.addEventListener("change", markDirty);
+  pixelAspectSel?.addEventListener("change", markDirty);
+  hardwareSel?.addEventListener("change", markDirty);
+  hardwarePaletteSel?.addEventListener("change", markDirty);
   tone?.addEventListener("input", markDirty);
   denoise?.addEventListener("input", markDirty);
   optAddBlack?.addEventListener("change", markDirty);
   optAddWhite?.addEventListener("change", markDirty);
   optInvert?.addEventListener("change", markDirty);
   optNightVision?.addEventListener("change", markDirty);
+  lutSel?.addEventListener("change", markDirty);
+  lutStrength?.addEventListener("input", markDirty);
+  alphaModeSel?.addEventListener("change", markDirty);
+  alphaThreshold?.addEventListener("input", markDirty);
+  optIndexedExport?.addEventListener("change", markDirty);
+  optTransparentSlot?.addEventListener("change", markDirty);
+  exportScale?.addEventListener("change", markDirty);
+  exportFilter?.addEventListener("change", markDirty);
+
+  // Eagle and Super-xBR only scale by 2 (4x runs them twice), so 3x isn't offered for them
+  function updateExportScaleOptions() {
+    if (!exportScale || !exportFilter) return;
+    const twoXOnly = exportFilter.value === "eagle" || exportFilter.value === "super-xbr";
+    const opt3 = exportScale.querySelector<HTMLOptionElement>('option[value="3"]');
+    if (opt3) opt3.disabled = twoXOnly;
+    if (twoXOnly && exportScale.value === "3") exportScale.value = "2";
+  }
+  updateExportScaleOptions();
+  exportFilter?.addEventListener("change", updateExportScaleOptions);
 
   // ---------------- Filters page wiring ----------------
   let filtersImage: string | null = null;
@@ -377,7 +533,7 @@ window.addEventListener("DOMContentLoaded", async () => {
 
   async function filtersHandleFile(file: File) {
     try {
-      filtersImage = await loadImageWithCorrectOrientation(file);
+      filtersImage = await readFileAsDataURL(file);
       if (fThumb && fDropHint) {
         fThumb.src = filtersImage;
         fThumb.style.display = "";